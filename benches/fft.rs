@@ -0,0 +1,135 @@
+//! Compares the real FFT path of `correlation_match` with the complex `Fft` it replaced.
+//!
+//! Run with `cargo bench --bench fft`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use sarus_editor_plugin::correlation_match::{
+    complex::{Complex, IMAG_UNIT},
+    cross_correlation::CrossCorrelation,
+    display::WeightWindow,
+    fft::Fft,
+    real_fft::RealFft,
+    CorrelationMatch,
+};
+
+const SIZES: [usize; 3] = [256, 1024, 4096];
+
+fn signal(size: usize) -> Vec<f32> {
+    (0..size)
+        .map(|i| (i as f32 * 0.05).sin() + 0.3 * (i as f32 * 0.31).sin())
+        .collect()
+}
+
+/// Cross correlation the way it was done with the complex `Fft`: both signals packed into one
+/// complex transform, followed by a complex inverse transform.
+struct ComplexCrossCorrelation {
+    fft: Fft,
+    buffer: Vec<Complex>,
+}
+
+impl ComplexCrossCorrelation {
+    fn new(max_size: usize) -> Self {
+        let fft_size = max_size.next_power_of_two() * 2;
+        ComplexCrossCorrelation {
+            fft: Fft::new(fft_size),
+            buffer: vec![(0., 0.).into(); fft_size],
+        }
+    }
+
+    fn compute_truncated(&mut self, a: &[f32], b: &[f32]) -> f32 {
+        let fft_size = self.buffer.len();
+        for (k, z) in self.buffer.iter_mut().enumerate() {
+            *z = (
+                a.get(k).copied().unwrap_or(0.),
+                b.get(k).copied().unwrap_or(0.),
+            )
+                .into();
+        }
+        self.fft.fft(&mut self.buffer);
+        let (left, right) = self.buffer.split_at_mut(fft_size / 2);
+        for zw in [&mut left[0], &mut right[0]].iter_mut() {
+            let Complex { real: aw, imag: bw } = **zw;
+            **zw = (aw * bw, 0.).into();
+        }
+        for (zw, zmw) in left[1..].iter_mut().zip(right[1..].iter_mut().rev()) {
+            let aw = (*zw + zmw.conj()) / 2.;
+            let bw = (zmw.conj() - *zw) * IMAG_UNIT / 2.;
+            let res = aw * bw.conj();
+            *zw = res;
+            *zmw = res.conj();
+        }
+        self.fft.ifft(&mut self.buffer);
+        self.buffer[..a.len() - b.len() + 1]
+            .iter()
+            .map(|z| z.real)
+            .sum()
+    }
+}
+
+fn fft_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("fft");
+    for size in SIZES.iter().copied() {
+        let input = signal(size);
+        group.bench_with_input(BenchmarkId::new("complex", size), &size, |bencher, _| {
+            let fft = Fft::new(size);
+            let mut buffer: Vec<Complex> = vec![(0., 0.).into(); size];
+            bencher.iter(|| {
+                for (z, x) in buffer.iter_mut().zip(input.iter()) {
+                    *z = (*x, 0.).into();
+                }
+                fft.fft(black_box(&mut buffer));
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("real", size), &size, |bencher, _| {
+            let mut fft = RealFft::new(size);
+            let mut re = vec![0.; fft.spectrum_size()];
+            let mut im = vec![0.; fft.spectrum_size()];
+            bencher.iter(|| fft.forward(black_box(&input), &mut re, &mut im))
+        });
+    }
+    group.finish();
+}
+
+fn cross_correlation_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cross_correlation");
+    for size in SIZES.iter().copied() {
+        let a = signal(size);
+        let b = &a[..size * 3 / 4];
+        group.bench_with_input(BenchmarkId::new("complex", size), &size, |bencher, _| {
+            let mut cross_correlation = ComplexCrossCorrelation::new(size);
+            bencher.iter(|| cross_correlation.compute_truncated(black_box(&a), black_box(b)))
+        });
+        group.bench_with_input(BenchmarkId::new("real", size), &size, |bencher, _| {
+            let mut cross_correlation = CrossCorrelation::new(size);
+            bencher.iter(|| {
+                cross_correlation
+                    .compute_truncated(black_box(&a), black_box(b))
+                    .sum::<f32>()
+            })
+        });
+    }
+    group.finish();
+}
+
+/// One match of a debugger waveform, as done every frame for each channel with smoothing on
+fn correlation_match_benches(c: &mut Criterion) {
+    let mut group = c.benchmark_group("correlation_match");
+    for size in SIZES.iter().copied() {
+        let a = signal(size);
+        let memory = &a[..size * 3 / 4];
+        let weight = WeightWindow::Hann.weights(memory.len());
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |bencher, _| {
+            let mut correlation_match = CorrelationMatch::new(size);
+            bencher.iter(|| correlation_match.compute(black_box(&a), memory, &weight))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    fft_benches,
+    cross_correlation_benches,
+    correlation_match_benches
+);
+criterion_main!(benches);
@@ -3,10 +3,31 @@ use std::{path::Path, process::Command, sync::atomic::Ordering};
 use egui::{Key, Ui};
 use log::info;
 
-use crate::compiler_interface::CompilerEditorState;
+use crate::{
+    compiler_interface::CompilerEditorState, console::console_ui, shortcuts::binding_pressed,
+};
+
+const MAX_FIND_LINES: usize = 20;
 
 pub fn code_editor_ui(ui: &mut Ui, state: &mut CompilerEditorState) {
     let mut errors = state.errors_buf_out.lock().unwrap().read().clone();
+    let (save_pressed, new_pressed, reload_pressed, find_pressed) =
+        match state.shared_ctx.projects.try_lock() {
+            Ok(projects) => {
+                let shortcuts = &projects.config.shortcuts;
+                let input = ui.input();
+                (
+                    binding_pressed(&shortcuts.save, &input),
+                    binding_pressed(&shortcuts.new, &input),
+                    binding_pressed(&shortcuts.reload, &input),
+                    binding_pressed(&shortcuts.find, &input),
+                )
+            }
+            Err(_) => (false, false, false, false),
+        };
+    if find_pressed && state.find.is_none() {
+        state.find = Some(String::new());
+    }
     if state.new_file_name.is_none() {
         ui.horizontal(|ui| {
             if ui.button("Show file").clicked() {
@@ -14,15 +35,13 @@ pub fn code_editor_ui(ui: &mut Ui, state: &mut CompilerEditorState) {
                     open_file(&projects.project_paths.projects_dir);
                 }
             }
-            if ui.button("Reload File").clicked() {
+            if ui.button("Reload File").clicked() || reload_pressed {
                 if let Ok(ref mut projects) = state.shared_ctx.projects.lock() {
                     if let Err(e) = projects.reload() {
                         state.errors = format!("Load File Error {}", e.to_string())
                     }
                 }
-            } else if ui.button("Save File").clicked()
-                || (ui.input().key_down(Key::S) && ui.input().modifiers.ctrl)
-            {
+            } else if ui.button("Save File").clicked() || save_pressed {
                 if let Ok(ref mut projects) = state.shared_ctx.projects.lock() {
                     let id = state.shared_ctx.project_float_id.get_u64();
                     match projects.set_code_by_id(id, state.code.replace("\t", "    ").to_string())
@@ -46,7 +65,7 @@ pub fn code_editor_ui(ui: &mut Ui, state: &mut CompilerEditorState) {
                         Err(e) => state.errors = format!("Load Save Error {}", e.to_string()),
                     }
                 }
-            } else if ui.button("New File").clicked() {
+            } else if ui.button("New File").clicked() || new_pressed {
                 state.new_file_name = Some("".to_string());
             }
 
@@ -88,17 +107,29 @@ pub fn code_editor_ui(ui: &mut Ui, state: &mut CompilerEditorState) {
             );
         }
     });
+    find_ui(ui, state, find_pressed);
     let new_project_float_id = state.shared_ctx.project_float_id.get_u64();
     if state.last_project_float_id != new_project_float_id {
-        state.last_project_float_id = new_project_float_id;
-        if let Ok(ref mut projects) = state.shared_ctx.projects.lock() {
+        // Retried next frame if either is busy
+        if let (Ok(ref mut projects), Ok(ref mut debug_out)) = (
+            state.shared_ctx.projects.try_lock(),
+            state.debug_out.try_lock(),
+        ) {
+            state.last_project_float_id = new_project_float_id;
             if let Some(code) = projects.get_code_from_id(new_project_float_id) {
                 state.code = code.to_string();
                 state.line_numbers = "".to_string();
+                if !state.file_name.is_empty() {
+                    projects
+                        .config
+                        .debug_layouts
+                        .insert(state.file_name.clone(), debug_out.layout());
+                }
                 state.file_name = projects
                     .get_name_from_id(new_project_float_id)
                     .unwrap()
                     .to_string();
+                debug_out.set_layout(projects.get_debug_layout(&state.file_name));
             }
         }
     }
@@ -142,6 +173,11 @@ pub fn code_editor_ui(ui: &mut Ui, state: &mut CompilerEditorState) {
                     .text_style(egui::TextStyle::Monospace), // for cursor height
             );
         });
+    ui.collapsing("Console", |ui| {
+        if let Ok(mut debug_out) = state.debug_out.try_lock() {
+            console_ui(ui, &mut debug_out.console);
+        }
+    });
     egui::ScrollArea::vertical()
         .enable_scrolling(true)
         .always_show_scroll(true)
@@ -195,6 +231,53 @@ pub fn code_editor_ui(ui: &mut Ui, state: &mut CompilerEditorState) {
     }
 }
 
+/// Search bar listing the lines of the code that contain the search text
+fn find_ui(ui: &mut Ui, state: &mut CompilerEditorState, focus: bool) {
+    let mut close = false;
+    if let Some(find) = &mut state.find {
+        let lines = if find.is_empty() {
+            Vec::new()
+        } else {
+            state
+                .code
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| line.contains(find.as_str()))
+                .map(|(i, _)| (i + 1).to_string())
+                .collect::<Vec<String>>()
+        };
+        ui.horizontal(|ui| {
+            ui.label("Find");
+            let response = ui.add(
+                egui::TextEdit::singleline(find)
+                    .desired_width(300.0)
+                    .text_style(egui::TextStyle::Monospace),
+            );
+            if focus {
+                response.request_focus();
+            }
+            if lines.len() > MAX_FIND_LINES {
+                ui.label(format!(
+                    "{} matches, lines {} ...",
+                    lines.len(),
+                    lines[..MAX_FIND_LINES].join(", ")
+                ));
+            } else {
+                ui.label(format!(
+                    "{} matches, lines {}",
+                    lines.len(),
+                    lines.join(", ")
+                ));
+            }
+            close = ui.button("Close").clicked()
+                || (response.has_focus() && ui.input().key_pressed(Key::Escape));
+        });
+    }
+    if close {
+        state.find = None;
+    }
+}
+
 fn setup_line_numbers(state: &mut CompilerEditorState) {
     if state.code.matches("\n").count() != state.line_numbers.matches("\n").count() {
         state.line_numbers = (0..state.code.matches("\n").count())
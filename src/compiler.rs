@@ -13,9 +13,9 @@ use sarus::{
 };
 
 use crate::{
-    heap_data::Heap,
-    sarus_egui_lib::{append_egui, DebuggerInput},
-    SarusDSPModelParams, SarusSharedState, SarusUIModelParams,
+    debugger::DebuggerInput, heap_data::Heap, sarus_egui_lib::append_egui,
+    shared_data::DSPSharedRef, state_inspector::StateLayout, SarusDSPModelParams, SarusSharedState,
+    SarusUIModelParams,
 };
 
 use triple_buffer::Input;
@@ -23,24 +23,35 @@ use triple_buffer::Input;
 pub const DEFAULT_CODE: &str = include_str!("../resources/example.sarus");
 pub const START_CODE: &str = include_str!("../resources/start.sarus");
 
-pub fn compile(ast: Vec<Declaration>, file_index_table: Vec<PathBuf>) -> anyhow::Result<JIT> {
-    let jit = default_std_jit_from_code_with_importer(
-        ast,
-        Some(file_index_table),
-        |ast, jit_builder| {
-            append_egui(ast, jit_builder);
-            let code = r#"
-struct AudioData { in_left: &[f32], in_right: &[f32], out_left: &[f32], out_right: &[f32], len: i64, sample_rate: f32, }
+/// Structs shared between Rust and Sarus, declared for every script
+pub const SARUS_STRUCTS: &str = r#"
+struct UIShared { ptr: &, }
+struct DSPShared { ptr: &, }
+struct Text { ptr: &, }
+struct TextInput { bytes: [i64; 32], }
+struct Curve { x: [f32; 32], y: [f32; 32], tension: [f32; 32], len: i64, }
+struct AudioData { in_left: &[f32], in_right: &[f32], out_left: &[f32], out_right: &[f32], len: i64, sample_rate: f32, shared: DSPShared, }
 struct Ui { ui: &, }
 struct Debugger {}
 struct SarusUIModelParams { p1: f32, p2: f32, p3: f32, p4: f32, p5: f32, p6: f32, p7: f32, p8: f32, 
-                            p9: f32, p10: f32, p11: f32, p12: f32, p13: f32, p14: f32, p15: f32, p16: f32,}
+                            p9: f32, p10: f32, p11: f32, p12: f32, p13: f32, p14: f32, p15: f32, p16: f32,
+                            shared: UIShared, text: Text,
+                            p1_changed: bool, p2_changed: bool, p3_changed: bool, p4_changed: bool, p5_changed: bool, p6_changed: bool, p7_changed: bool, p8_changed: bool,
+                            p9_changed: bool, p10_changed: bool, p11_changed: bool, p12_changed: bool, p13_changed: bool, p14_changed: bool, p15_changed: bool, p16_changed: bool,
+                            gesture_begin: i64, gesture_end: i64,}
 struct SarusDSPModelParams { p1: &[f32], p2: &[f32], p3: &[f32], p4: &[f32], p5: &[f32], p6: &[f32], p7: &[f32], p8: &[f32], 
                              p9: &[f32], p10: &[f32], p11: &[f32], p12: &[f32], p13: &[f32], p14: &[f32], p15: &[f32], p16: &[f32],
                              p1_active: bool, p2_active: bool, p3_active: bool, p4_active: bool, p5_active: bool, p6_active: bool, p7_active: bool, p8_active: bool, 
                              p9_active: bool, p10_active: bool, p11_active: bool, p12_active: bool, p13_active: bool, p14_active: bool, p15_active: bool, p16_active: bool,}
 "#;
-            ast.append(&mut parse(&code).unwrap());
+
+pub fn compile(ast: Vec<Declaration>, file_index_table: Vec<PathBuf>) -> anyhow::Result<JIT> {
+    let jit = default_std_jit_from_code_with_importer(
+        ast,
+        Some(file_index_table),
+        |ast, jit_builder| {
+            append_egui(ast, jit_builder);
+            ast.append(&mut parse(SARUS_STRUCTS).unwrap());
         },
     )?;
     Ok(jit)
@@ -54,12 +65,15 @@ pub struct AudioData {
     pub out_right: *const f32,
     pub len: i64,
     pub sample_rate: f32,
+    pub shared: DSPSharedRef,
 }
 
 #[derive(Clone)]
 pub struct CompiledUIPayload {
     pub editor_func: extern "C" fn(&mut Ui, &mut SarusUIModelParams, *mut u8),
     pub editor_data: Heap,
+    /// Counts up with each compile, the shared data tables are reset when it changes
+    pub shared_generation: usize,
 }
 
 #[derive(Clone)]
@@ -67,6 +81,8 @@ pub struct CompiledDSPPayload {
     pub process_func:
         extern "C" fn(&mut SarusDSPModelParams, &mut AudioData, *mut u8, &mut DebuggerInput),
     pub process_data: Heap,
+    /// Same as `CompiledUIPayload::shared_generation`
+    pub shared_generation: usize,
 }
 
 pub fn init_compiler_thread(
@@ -82,6 +98,7 @@ pub fn init_compiler_thread(
         let mut last_audio_thread_float_id = shared_ctx.audio_thread_float_id.get_u64();
         let mut _editor_jit = None; //These are only kept around so the deep stack is not dropped
         let mut _process_jit = None;
+        let mut shared_generation = 0;
         loop {
             let new_project_float_id = shared_ctx.project_float_id.get_u64();
             let new_audio_thread_float_id = shared_ctx.audio_thread_float_id.get_u64();
@@ -124,12 +141,23 @@ pub fn init_compiler_thread(
                         match start_compile(
                             code.to_string(),
                             &projects.project_paths.projects_dir.join(path),
+                            shared_generation + 1,
                         ) {
                             Ok((ui_payload, dsp_payload, new_editor_jit, new_process_jit)) => {
                                 ::log::info!("Compile Successful");
+                                shared_generation += 1;
                                 errors_buf_in.write(String::from("Compile Successful"));
+                                let compiled_size = dsp_payload.process_data.size();
                                 ui_payload_in.write(Some(ui_payload));
                                 dsp_payload_in.write(Some(dsp_payload));
+                                let mut layout = StateLayout::from_code(
+                                    &format!("{}\n{}", code, SARUS_STRUCTS),
+                                    "ProcessState",
+                                );
+                                if let Some(layout) = &mut layout {
+                                    layout.compiled_size = compiled_size;
+                                }
+                                *shared_ctx.process_layout.lock().unwrap() = layout;
                                 _editor_jit = Some(new_editor_jit);
                                 _process_jit = Some(new_process_jit);
                             }
@@ -149,6 +177,7 @@ pub fn init_compiler_thread(
 fn start_compile(
     code: String,
     file: &Path,
+    shared_generation: usize,
 ) -> anyhow::Result<(CompiledUIPayload, CompiledDSPPayload, JIT, JIT)> {
     info!("Compiling {:?}", file);
     //TODO don't compile things like process and editor twice
@@ -170,10 +199,12 @@ fn start_compile(
     let ui_payload = CompiledUIPayload {
         editor_func,
         editor_data: get_state(&mut editor_jit, "EditorState::size", "init_editor_state")?,
+        shared_generation,
     };
     let dsp_payload = CompiledDSPPayload {
         process_func,
         process_data: get_state(&mut process_jit, "ProcessState::size", "init_process_state")?,
+        shared_generation,
     };
     Ok((ui_payload, dsp_payload, editor_jit, process_jit))
 }
@@ -5,27 +5,121 @@ use std::{
 };
 
 use baseview::{Size, WindowOpenOptions, WindowScalePolicy};
-use egui::{Align, CtxRef, Direction, FontDefinitions, Layout};
+use egui::{Align, CtxRef, Direction, FontDefinitions, Layout, Ui};
 use egui_baseview::{EguiWindow, Queue, RenderSettings, Settings};
 
 use crate::{
     code_editor::code_editor_ui,
     compiler::{init_compiler_thread, CompiledDSPPayload, CompiledUIPayload, DEFAULT_CODE},
-    correlation_match::display::DisplayBuffer,
+    correlation_match::display::{DisplayBuffer, WeightWindow},
+    debugger::DebuggerOutput,
     graphs::graphs_ui,
-    sarus_egui_lib::DebuggerOutput,
+    recorder::Recorder,
+    shortcuts::binding_pressed,
     SarusSharedState,
 };
 
+use log::error;
 use triple_buffer::{Input, Output, TripleBuffer};
 
+pub const MIN_WAVEFORM_SIZE: usize = 16;
+
 pub struct WaveformDisplay {
-    pub buffer: DisplayBuffer,
+    // Allocated by the first update, so channels that are never shown don't pay for it
+    buffer: Option<DisplayBuffer>,
     pub display_decay: f32,
     pub memory_decay: f32,
     pub enable_waveform: bool,
     pub enable_smoothing: bool,
     pub offset: usize,
+    pub window: WeightWindow,
+    /// Number of samples shown, up to the length of the channel
+    pub display_size: usize,
+    /// New samples needed before the waveform is matched again, 0 matches every frame
+    pub match_interval: usize,
+    /// Names of channels drawn lined up with this one, which acts as their trigger
+    pub aligned: Vec<String>,
+    pending: usize,
+}
+
+impl WaveformDisplay {
+    pub fn new(size: usize) -> Self {
+        WaveformDisplay {
+            buffer: None,
+            display_decay: 0.6,
+            memory_decay: 0.8,
+            enable_waveform: true,
+            enable_smoothing: false,
+            offset: 0,
+            window: WeightWindow::Hann,
+            display_size: size * 3 / 4,
+            match_interval: 0,
+            aligned: Vec::new(),
+            pending: 0,
+        }
+    }
+
+    /// Match and display the latest values of the trigger channel and the `aligned` channels,
+    /// oldest first. `new_values` of the trigger channel arrived since the last update.
+    ///
+    /// The input of the match is a third longer than the display, so it doesn't depend on the
+    /// length of the channel.
+    pub fn update(&mut self, trigger: &[f32], aligned: &[&[f32]], new_values: usize) {
+        let display_size = self
+            .display_size
+            .clamp(MIN_WAVEFORM_SIZE, trigger.len().max(MIN_WAVEFORM_SIZE));
+        let input_size = (display_size * 4 / 3).min(trigger.len()).max(display_size);
+        let resized = match &self.buffer {
+            Some(buffer) => {
+                buffer.display_size() != display_size || buffer.input_size() != input_size
+            }
+            None => true,
+        };
+        if resized {
+            self.buffer = None;
+            self.pending = self.match_interval;
+        }
+        let window = self.window;
+        let buffer = self.buffer.get_or_insert_with(|| {
+            DisplayBuffer::with_channels(aligned.len() + 1, input_size, display_size, window)
+        });
+        buffer.set_window(self.window);
+        buffer.set_channels(aligned.len() + 1);
+        self.pending += new_values;
+        if self.pending >= self.match_interval {
+            self.pending = 0;
+            for (channel, data) in std::iter::once(trigger)
+                .chain(aligned.iter().copied())
+                .enumerate()
+            {
+                let buffer = buffer.get_channel_buffer_mut(channel);
+                // Line up the newest values, channels shorter than the input start with zeros
+                let skip = data.len().saturating_sub(input_size);
+                let pad = input_size.saturating_sub(data.len());
+                buffer[..pad].iter_mut().for_each(|v| *v = 0.0);
+                buffer[pad..].copy_from_slice(&data[skip..]);
+            }
+            buffer.update_match(
+                true,
+                decay_time_to_factor(self.memory_decay),
+                decay_time_to_factor(self.display_decay),
+            );
+        }
+        buffer.update_display(decay_time_to_factor(self.display_decay));
+    }
+
+    /// Display of channel `channel`, 0 is the trigger. Empty until the first update.
+    pub fn channel_display(&self, channel: usize) -> &[f32] {
+        match &self.buffer {
+            Some(buffer) => buffer.get_channel_display(channel),
+            None => &[],
+        }
+    }
+}
+
+fn decay_time_to_factor(time: f32) -> f32 {
+    // arbitrary constant that gives a useful range
+    1. - (-1. / 6. / time).exp()
 }
 
 pub struct CompilerEditorState {
@@ -36,11 +130,15 @@ pub struct CompilerEditorState {
     pub errors_buf_out: Arc<Mutex<triple_buffer::Output<String>>>,
     pub shared_ctx: Arc<SarusSharedState>,
     pub debug_out: Arc<Mutex<DebuggerOutput>>,
-    pub waveforms: Vec<WaveformDisplay>,
     pub last_project_float_id: u64,
     pub new_file_name: Option<String>,
     pub compile_on_save: bool,
     pub file_name: String,
+    pub last_screen_size: egui::Vec2,
+    /// Text of the find bar, if it's open
+    pub find: Option<String>,
+    /// Outlives the window so recordings keep going while it's closed
+    pub recorder: Arc<Mutex<Recorder>>,
 }
 
 pub fn setup_fonts(ctx: &CtxRef) {
@@ -52,6 +150,25 @@ pub fn setup_fonts(ctx: &CtxRef) {
     ctx.set_fonts(fonts);
 }
 
+fn editor_window_size_ui(ui: &mut Ui, state: &mut CompilerEditorState) {
+    ui.collapsing("Window Size", |ui| {
+        if let Ok(mut projects) = state.shared_ctx.projects.try_lock() {
+            let size = &mut projects.config.editor_window_size;
+            ui.add(
+                egui::DragValue::new(&mut size[0])
+                    .clamp_range(400.0..=8192.0)
+                    .suffix(" px"),
+            );
+            ui.add(
+                egui::DragValue::new(&mut size[1])
+                    .clamp_range(300.0..=8192.0)
+                    .suffix(" px"),
+            );
+            ui.label("The new size is used when the editor is reopened");
+        }
+    });
+}
+
 pub fn init_compiler_editor_thread(
     ui_payload_in: Input<Option<CompiledUIPayload>>,
     dsp_payload_in: Input<Option<CompiledDSPPayload>>,
@@ -81,30 +198,25 @@ fn init_code_editor_thread(
     shared_ctx: Arc<SarusSharedState>,
 ) {
     thread::spawn(move || {
+        let recorder = Arc::new(Mutex::new(Recorder::default()));
         loop {
             if shared_ctx.code_editor_is_open.load(Ordering::Relaxed) {
                 {
+                    let [width, height] = shared_ctx
+                        .projects
+                        .lock()
+                        .unwrap()
+                        .config
+                        .editor_window_size;
                     let settings = Settings {
                         window: WindowOpenOptions {
                             title: String::from("egui-baseplug-examples gain"),
-                            size: Size::new(1800.0, 1600.0),
+                            size: Size::new(width as f64, height as f64),
                             scale: WindowScalePolicy::SystemScaleFactor,
                         },
                         render_settings: RenderSettings::default(),
                     };
 
-                    let mut waveforms = Vec::new();
-                    for _ in 0..4 {
-                        waveforms.push(WaveformDisplay {
-                            buffer: DisplayBuffer::new(1024, 768),
-                            display_decay: 0.6,
-                            memory_decay: 0.8,
-                            enable_waveform: true,
-                            enable_smoothing: false,
-                            offset: 0,
-                        });
-                    }
-
                     EguiWindow::open_blocking(
                         settings,
                         CompilerEditorState {
@@ -115,11 +227,13 @@ fn init_code_editor_thread(
                             shared_ctx: shared_ctx.clone(),
                             debug_out: debug_out.clone(),
                             file_saved: true,
-                            waveforms,
                             last_project_float_id: 0,
                             new_file_name: None,
                             compile_on_save: true,
                             file_name: "".to_string(),
+                            last_screen_size: egui::vec2(width, height),
+                            find: None,
+                            recorder: recorder.clone(),
                         },
                         // Called once before the first frame. Allows you to do setup code and to
                         // call `ctx.set_fonts()`. Optional.
@@ -135,6 +249,15 @@ fn init_code_editor_thread(
                         // Called before each frame. Here you should update the state of your
                         // application and build the UI.
                         |ctx: &CtxRef, _queue: &mut Queue, state: &mut CompilerEditorState| {
+                            // Keep track of the window being resized so it can be saved on close
+                            let screen_size = ctx.input().screen_rect().size();
+                            if screen_size != state.last_screen_size {
+                                state.last_screen_size = screen_size;
+                                if let Ok(mut projects) = state.shared_ctx.projects.try_lock() {
+                                    projects.config.editor_window_size =
+                                        [screen_size.x, screen_size.y];
+                                }
+                            }
                             egui::SidePanel::left("Debug")
                                 .default_width(500.0)
                                 .show(ctx, |ui| {
@@ -144,8 +267,16 @@ fn init_code_editor_thread(
                                     )
                                     .with_cross_justify(true);
                                     ui.checkbox(&mut state.compile_on_save, "Compile On Save");
+                                    let compile_pressed = match state.shared_ctx.projects.try_lock()
+                                    {
+                                        Ok(projects) => binding_pressed(
+                                            &projects.config.shortcuts.compile,
+                                            &ui.input(),
+                                        ),
+                                        Err(_) => false,
+                                    };
                                     ui.with_layout(layout, |ui| {
-                                        if ui.button("COMPILE").clicked() {
+                                        if ui.button("COMPILE").clicked() || compile_pressed {
                                             state.errors = String::from("");
                                             state
                                                 .shared_ctx
@@ -153,6 +284,7 @@ fn init_code_editor_thread(
                                                 .store(true, Ordering::Relaxed);
                                         }
                                     });
+                                    editor_window_size_ui(ui, state);
                                     graphs_ui(ui, state)
                                 });
                             egui::CentralPanel::default().show(ctx, |ui| {
@@ -163,6 +295,15 @@ fn init_code_editor_thread(
                         },
                     );
                 }
+                let mut projects = shared_ctx.projects.lock().unwrap();
+                let project_id = shared_ctx.project_float_id.get_u64();
+                if let Some(name) = projects.get_name_from_id(project_id).map(str::to_string) {
+                    let layout = debug_out.lock().unwrap().layout();
+                    projects.config.debug_layouts.insert(name, layout);
+                }
+                if let Err(e) = projects.update_config() {
+                    error!("Could not save config file! {}", e);
+                }
                 shared_ctx
                     .code_editor_is_open
                     .store(false, Ordering::Relaxed)
@@ -0,0 +1,228 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use egui::{Color32, TextStyle, Ui};
+use ringbuf::{Consumer, Producer, RingBuffer};
+
+/// Longer messages are truncated
+pub const MAX_MESSAGE_LEN: usize = 120;
+const CONSOLE_QUEUE_SIZE: usize = 256;
+/// Messages printed after this many in one block or frame are dropped
+pub const MAX_MESSAGES_PER_UPDATE: usize = 16;
+const MAX_CONSOLE_LINES: usize = 1000;
+const CONSOLE_HEIGHT: f32 = 150.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogSource {
+    Process,
+    Editor,
+}
+
+/// Value printed after the message text
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogValue {
+    None,
+    Float(f32),
+    Int(i64),
+    Bool(bool),
+}
+
+/// Fixed size so messages can be sent from the audio thread without allocating
+struct LogMessage {
+    time: Instant,
+    text: [u8; MAX_MESSAGE_LEN],
+    len: usize,
+    value: LogValue,
+}
+
+impl LogMessage {
+    fn format(&self) -> String {
+        let text = String::from_utf8_lossy(&self.text[..self.len]);
+        match self.value {
+            LogValue::None => text.to_string(),
+            LogValue::Float(v) => format!("{} {}", text, v),
+            LogValue::Int(v) => format!("{} {}", text, v),
+            LogValue::Bool(v) => format!("{} {}", text, v),
+        }
+    }
+}
+
+/// Sending side of the console. The process function has one in `DebuggerInput`, the editor
+/// function one in `UISharedData`.
+pub struct ConsoleInput {
+    producer: Producer<LogMessage>,
+    sent: usize,
+    dropped: Arc<AtomicUsize>,
+}
+
+impl ConsoleInput {
+    /// Returns false if the message was dropped because the queue is full or too many messages
+    /// were printed since the last `update`. Doesn't allocate.
+    pub fn print(&mut self, text: &str, value: LogValue) -> bool {
+        if self.sent >= MAX_MESSAGES_PER_UPDATE || self.producer.is_full() {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return false;
+        }
+        self.sent += 1;
+        let mut len = text.len().min(MAX_MESSAGE_LEN);
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut message = LogMessage {
+            time: Instant::now(),
+            text: [0; MAX_MESSAGE_LEN],
+            len,
+            value,
+        };
+        message.text[..len].copy_from_slice(&text.as_bytes()[..len]);
+        self.producer.push(message).is_ok()
+    }
+
+    /// Reset the rate limit. Called at the start of each block or frame.
+    pub fn update(&mut self) {
+        self.sent = 0;
+    }
+}
+
+pub struct ConsoleLine {
+    /// Seconds since the console was created
+    pub time: f32,
+    pub source: LogSource,
+    pub text: String,
+    /// How many times in a row this line was printed
+    pub count: usize,
+}
+
+/// Editor side of the console, shown in the code editor window.
+pub struct ConsoleOutput {
+    start: Instant,
+    inputs: Vec<(LogSource, Consumer<LogMessage>, Arc<AtomicUsize>)>,
+    pub lines: VecDeque<ConsoleLine>,
+    /// Messages lost to the rate limit or a full queue
+    pub dropped: usize,
+}
+
+impl Default for ConsoleOutput {
+    fn default() -> Self {
+        ConsoleOutput {
+            start: Instant::now(),
+            inputs: Vec::new(),
+            lines: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+}
+
+impl ConsoleOutput {
+    pub fn new_input(&mut self, source: LogSource) -> ConsoleInput {
+        let (producer, consumer) = RingBuffer::new(CONSOLE_QUEUE_SIZE).split();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        self.inputs.push((source, consumer, dropped.clone()));
+        ConsoleInput {
+            producer,
+            sent: 0,
+            dropped,
+        }
+    }
+
+    /// Pull in new messages. Repeats of the last line are counted instead of added.
+    pub fn update(&mut self) {
+        let mut messages = Vec::new();
+        for (source, consumer, dropped) in self.inputs.iter_mut() {
+            while let Some(message) = consumer.pop() {
+                messages.push((*source, message));
+            }
+            self.dropped += dropped.swap(0, Ordering::Relaxed);
+        }
+        messages.sort_by_key(|(_, message)| message.time);
+        for (source, message) in messages {
+            let time = message.time.duration_since(self.start).as_secs_f32();
+            let text = message.format();
+            match self.lines.back_mut() {
+                Some(last) if last.source == source && last.text == text => {
+                    last.time = time;
+                    last.count += 1;
+                }
+                _ => {
+                    self.lines.push_back(ConsoleLine {
+                        time,
+                        source,
+                        text,
+                        count: 1,
+                    });
+                    if self.lines.len() > MAX_CONSOLE_LINES {
+                        self.lines.pop_front();
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.dropped = 0;
+    }
+}
+
+pub fn console_ui(ui: &mut Ui, console: &mut ConsoleOutput) {
+    console.update();
+    ui.horizontal(|ui| {
+        if ui.button("Clear Console").clicked() {
+            console.clear();
+        }
+        if console.dropped > 0 {
+            ui.colored_label(
+                Color32::from_rgb(230, 140, 40),
+                format!("{} messages dropped", console.dropped),
+            );
+        }
+    });
+    egui::ScrollArea::vertical()
+        .enable_scrolling(true)
+        .max_height(CONSOLE_HEIGHT)
+        .stick_to_bottom()
+        .id_source("console")
+        .show(ui, |ui| {
+            for line in console.lines.iter() {
+                let source = match line.source {
+                    LogSource::Process => "process",
+                    LogSource::Editor => "editor",
+                };
+                let mut text = format!("{:10.3} {:7} {}", line.time, source, line.text);
+                if line.count > 1 {
+                    text += &format!(" (x{})", line.count);
+                }
+                ui.add(egui::Label::new(text).text_style(TextStyle::Monospace));
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_console() {
+        let mut console = ConsoleOutput::default();
+        let mut process = console.new_input(LogSource::Process);
+        let mut editor = console.new_input(LogSource::Editor);
+        assert!(process.print("gain", LogValue::Float(0.5)));
+        assert!(editor.print("open", LogValue::None));
+        for _ in 0..MAX_MESSAGES_PER_UPDATE {
+            process.print("tick", LogValue::Int(1));
+        }
+        console.update();
+        assert_eq!(console.lines.len(), 3);
+        assert_eq!(console.lines[0].text, "gain 0.5");
+        assert_eq!(console.lines[1].source, LogSource::Editor);
+        assert_eq!(console.lines[2].count, MAX_MESSAGES_PER_UPDATE - 1);
+        assert_eq!(console.dropped, 1);
+        process.update();
+        assert!(process.print("tick", LogValue::Bool(true)));
+    }
+}
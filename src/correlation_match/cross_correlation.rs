@@ -23,11 +23,8 @@ SOFTWARE.
 */
 
 //use crate::math::*;
-use std::array::IntoIter;
 
-use crate::correlation_match::complex::IMAG_UNIT;
-
-use super::{complex::Complex, fft};
+use super::real_fft::RealFft;
 
 /// Computes cross correlation efficiently, using FFT.
 ///
@@ -35,21 +32,31 @@ use super::{complex::Complex, fft};
 pub struct CrossCorrelation {
     base_size: usize,
     fft_size: usize,
-    fft: fft::Fft,
-    buffer: Vec<Complex>,
+    fft: RealFft,
+    buffer: Vec<f32>,
+    a_re: Vec<f32>,
+    a_im: Vec<f32>,
+    b_re: Vec<f32>,
+    b_im: Vec<f32>,
 }
 
 impl CrossCorrelation {
     /// Allocate and prepare a cross correlation. `max_size` is the maximum size
     /// of either of the input arrays.
     pub fn new(max_size: usize) -> Self {
-        let base_size = max_size.next_power_of_two();
+        let base_size = max_size.next_power_of_two().max(2);
         let fft_size = base_size * 2;
+        let fft = RealFft::new(fft_size);
+        let spectrum_size = fft.spectrum_size();
         CrossCorrelation {
             base_size,
             fft_size,
-            fft: fft::Fft::new(fft_size),
-            buffer: vec![(0., 0.).into(); fft_size],
+            fft,
+            buffer: vec![0.; fft_size],
+            a_re: vec![0.; spectrum_size],
+            a_im: vec![0.; spectrum_size],
+            b_re: vec![0.; spectrum_size],
+            b_im: vec![0.; spectrum_size],
         }
     }
 
@@ -65,7 +72,7 @@ impl CrossCorrelation {
         self.buffer[self.fft_size - b.len() + 1..]
             .iter()
             .chain(self.buffer[..a.len()].iter())
-            .map(|z| z.real)
+            .copied()
     }
 
     /// Compute cross correlation excluding partially overlapping positions.
@@ -74,56 +81,57 @@ impl CrossCorrelation {
     pub fn compute_truncated(&mut self, a: &[f32], b: &[f32]) -> impl Iterator<Item = f32> + '_ {
         assert!(a.len() >= b.len());
         self.compute_raw(a, b);
-        self.buffer[..a.len() - b.len() + 1].iter().map(|z| z.real)
+        self.buffer[..a.len() - b.len() + 1].iter().copied()
     }
 
-    /// Performs the computation without extracting results from the `buffer`.
+    /// Performs the computation, leaving the result in `buffer`.
     fn compute_raw(&mut self, a: &[f32], b: &[f32]) {
         assert!(a.len() <= self.base_size);
         assert!(b.len() <= self.base_size);
-        // We use a trick to perform FFTs for two non-complex signals at once.
-        //
-        // The arrays are packed as z[k] = (a[k] + i*b[k]), then z' = fft(z) is performed.
-        // Now z' = a' + i*b' where a' = fft(a) and b' = fft(b).
-        //
-        // For all frequencies w, a[w] and b[w] can be solved when
-        // a[w] + i*b[w] and a[-w] + i*b[-w] are known.
-        //
-        // The cross correlation requires computing a[w] * conj(b[w]) for each frequency,
-        // and then taking the inverse FFT.
-        use std::iter;
-        for (zk, (ak, bk)) in self.buffer.iter_mut().zip(
-            a.iter()
-                .cloned()
-                .chain(iter::repeat(0.))
-                .zip(b.iter().cloned().chain(iter::repeat(0.))),
-        ) {
-            *zk = (ak, bk).into();
+        // The cross correlation is the inverse FFT of a[w] * conj(b[w]). The signals are real,
+        // so only half of each spectrum is computed, see `RealFft`.
+        self.buffer[..a.len()].copy_from_slice(a);
+        self.buffer[a.len()..].iter_mut().for_each(|v| *v = 0.);
+        self.fft
+            .forward(&self.buffer, &mut self.a_re, &mut self.a_im);
+        self.buffer[..b.len()].copy_from_slice(b);
+        self.buffer[b.len()..].iter_mut().for_each(|v| *v = 0.);
+        self.fft
+            .forward(&self.buffer, &mut self.b_re, &mut self.b_im);
+
+        for ((a_re, a_im), (b_re, b_im)) in self
+            .a_re
+            .iter_mut()
+            .zip(self.a_im.iter_mut())
+            .zip(self.b_re.iter().zip(self.b_im.iter()))
+        {
+            let re = *a_re * b_re + *a_im * b_im;
+            let im = *a_im * b_re - *a_re * b_im;
+            *a_re = re;
+            *a_im = im;
         }
 
-        self.fft.fft(&mut self.buffer);
+        self.fft.inverse(&self.a_re, &self.a_im, &mut self.buffer);
+    }
+}
 
-        // Split buffer into left and right half because we need to iterate both at once.
-        let (left, right) = self.buffer.split_at_mut(self.fft_size / 2);
-        // a[0] = a[-0] and a[N/2] = a[-N/2] so they must be handled separately.
-        for zw in IntoIter::new([&mut left[0], &mut right[0]]) {
-            let Complex { real: aw, imag: bw } = *zw;
-            *zw = Complex {
-                real: aw * bw,
-                imag: 0.,
-            };
-        }
-        for (zw, zmw) in left[1..].iter_mut().zip(right[1..].iter_mut().rev()) {
-            // zw = z[w], zmw = z[-w]
-            // Solve a[w] and b[w] first
-            let aw = (*zw + zmw.conj()) / 2.;
-            let bw = (zmw.conj() - *zw) * IMAG_UNIT / 2.;
-            // Then store a[w] * conj(b[w]) and conj(a[w] * conj(b[w]))
-            let res = aw * bw.conj();
-            *zw = res;
-            *zmw = res.conj();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_cross_correlation() {
+        let a: Vec<f32> = (0..100).map(|i| (i as f32 * 0.37).sin()).collect();
+        let b: Vec<f32> = (0..30).map(|i| (i as f32 * 0.11).cos()).collect();
+        let mut cross_correlation = CrossCorrelation::new(100);
+        let truncated: Vec<f32> = cross_correlation.compute_truncated(&a, &b).collect();
+        assert_eq!(truncated.len(), a.len() - b.len() + 1);
+        for (t, result) in truncated.iter().enumerate() {
+            let expected: f32 = b.iter().enumerate().map(|(x, b)| a[x + t] * b).sum();
+            assert!((result - expected).abs() < 1e-3);
         }
-
-        self.fft.ifft(&mut self.buffer);
+        let full: Vec<f32> = cross_correlation.compute(&a, &b).collect();
+        assert_eq!(full.len(), a.len() + b.len() - 1);
+        // First position only overlaps a[0] and b[b.len() - 1]
+        assert!((full[0] - a[0] * b[b.len() - 1]).abs() < 1e-3);
     }
 }
@@ -26,16 +26,67 @@ SOFTWARE.
 
 use std::f32::consts::PI;
 
+use serde::{Deserialize, Serialize};
+
 use super::iter_windows::{shift_left, shift_left_fill, shift_right, shift_right_fill};
 use crate::correlation_match::CorrelationMatch;
 
+/// Weighting applied across the display when matching, deciding which part of the display is
+/// kept most stable.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum WeightWindow {
+    Hann,
+    Blackman,
+    Triangular,
+    /// Every sample of the display counts the same
+    Rectangular,
+}
+
+impl WeightWindow {
+    pub const ALL: [WeightWindow; 4] = [
+        WeightWindow::Hann,
+        WeightWindow::Blackman,
+        WeightWindow::Triangular,
+        WeightWindow::Rectangular,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            WeightWindow::Hann => "Hann",
+            WeightWindow::Blackman => "Blackman",
+            WeightWindow::Triangular => "Triangular",
+            WeightWindow::Rectangular => "Rectangular",
+        }
+    }
+
+    /// Weights of a display of `size` samples. All windows peak at 2.0 in the center.
+    pub fn weights(self, size: usize) -> Vec<f32> {
+        (0..size)
+            .map(|index| index as isize - (size / 2) as isize)
+            .map(|offset| offset as f32 / size as f32)
+            .map(|x| match self {
+                WeightWindow::Hann => 1. + (2. * PI * x).cos(),
+                WeightWindow::Blackman => {
+                    2. * (0.42 + 0.5 * (2. * PI * x).cos() + 0.08 * (4. * PI * x).cos())
+                }
+                WeightWindow::Triangular => 2. * (1. - 2. * x.abs()),
+                WeightWindow::Rectangular => 2.,
+            })
+            .collect()
+    }
+}
+
 /// Stores a prepared [`CorrelationMatch`] and buffers for display and memory.
+///
+/// Several channels can be displayed together. The first one is the trigger channel: only it is
+/// matched, and the others are shifted by the same offset so they stay lined up with it.
 pub struct DisplayBuffer {
     size: usize,
     correlation_matcher: CorrelationMatch,
-    buffer: Vec<f32>,
-    display: Vec<f32>,
+    buffers: Vec<Vec<f32>>,
+    displays: Vec<Vec<f32>>,
     memory: Vec<f32>,
+    window: WeightWindow,
     weight: Vec<f32>,
     offset: usize,
     residual: f32,
@@ -43,7 +94,7 @@ pub struct DisplayBuffer {
 }
 
 impl DisplayBuffer {
-    /// Construct a new [`DisplayBuffer`] with given input buffer size and display
+    /// Construct a new single channel [`DisplayBuffer`] with given input buffer size and display
     /// buffer size.
     ///
     /// `input_size` must be at least as learge as `display_size`.
@@ -51,57 +102,114 @@ impl DisplayBuffer {
     /// The weight function is populated with a Hann window, so the center of the display is
     /// prioritized when matching.
     pub fn new(input_size: usize, display_size: usize) -> Self {
+        Self::with_channels(1, input_size, display_size, WeightWindow::Hann)
+    }
+
+    /// Construct a [`DisplayBuffer`] for `channels` channels, the first being the trigger
+    /// channel, matched using `window`.
+    pub fn with_channels(
+        channels: usize,
+        input_size: usize,
+        display_size: usize,
+        window: WeightWindow,
+    ) -> Self {
         assert!(input_size >= display_size);
-        let weight = (0..display_size)
-            .map(|index| index as isize - (display_size / 2) as isize)
-            .map(|offset| offset as f32 / display_size as f32)
-            .map(|x| 1. + (2. * PI * x).cos())
-            .collect();
+        assert!(channels > 0);
         DisplayBuffer {
             size: display_size,
             correlation_matcher: CorrelationMatch::new(input_size),
-            buffer: vec![0.; input_size],
-            display: vec![0.; display_size],
+            buffers: vec![vec![0.; input_size]; channels],
+            displays: vec![vec![0.; display_size]; channels],
             memory: vec![0.; display_size],
-            weight,
+            window,
+            weight: window.weights(display_size),
             offset: 0,
             residual: 0.,
             average_period: 0.,
         }
     }
 
+    pub fn input_size(&self) -> usize {
+        self.buffers[0].len()
+    }
+
+    pub fn display_size(&self) -> usize {
+        self.size
+    }
+
+    pub fn channels(&self) -> usize {
+        self.buffers.len()
+    }
+
+    /// Add or remove channels after the trigger channel. New channels start out silent.
+    pub fn set_channels(&mut self, channels: usize) {
+        let channels = channels.max(1);
+        let input_size = self.input_size();
+        self.buffers.resize(channels, vec![0.; input_size]);
+        self.displays.resize(channels, vec![0.; self.size]);
+    }
+
+    pub fn window(&self) -> WeightWindow {
+        self.window
+    }
+
+    pub fn set_window(&mut self, window: WeightWindow) {
+        if window != self.window {
+            self.window = window;
+            self.weight = window.weights(self.size);
+        }
+    }
+
     /// Scroll all internal buffers by the given signed amount of samples, to the right.
     ///
     /// Missing data is retrieved from the input buffer, or replaced with zeros if not available.
     pub fn scroll(&mut self, amount: i32) {
-        match amount {
-            amount if amount > 0 => {
-                let amount = amount as usize;
-                shift_right_fill(&mut self.buffer, amount, 0.);
-                let replace_range = &self.buffer[self.offset..][..amount];
-                shift_right(&mut self.display, replace_range);
-                shift_right(&mut self.memory, replace_range);
-            }
-            amount if amount < 0 => {
-                let amount = -amount as usize;
-                shift_left_fill(&mut self.buffer, amount, 0.);
-                let replace_range = &self.buffer[self.offset + self.size - amount..][..amount];
-                shift_left(&mut self.display, replace_range);
-                shift_left(&mut self.memory, replace_range);
+        let (offset, size) = (self.offset, self.size);
+        for (channel, (buffer, display)) in self
+            .buffers
+            .iter_mut()
+            .zip(self.displays.iter_mut())
+            .enumerate()
+        {
+            match amount {
+                amount if amount > 0 => {
+                    let amount = amount as usize;
+                    shift_right_fill(buffer, amount, 0.);
+                    let replace_range = &buffer[offset..][..amount];
+                    shift_right(display, replace_range);
+                    if channel == 0 {
+                        shift_right(&mut self.memory, replace_range);
+                    }
+                }
+                amount if amount < 0 => {
+                    let amount = -amount as usize;
+                    shift_left_fill(buffer, amount, 0.);
+                    let replace_range = &buffer[offset + size - amount..][..amount];
+                    shift_left(display, replace_range);
+                    if channel == 0 {
+                        shift_left(&mut self.memory, replace_range);
+                    }
+                }
+                _ => {}
             }
-            _ => {}
         }
     }
 
-    /// Get a mutable reference to the input buffer. If it is mutated, remember to call
-    /// [`update_match`](Self::update_match) afterwards.
+    /// Get a mutable reference to the input buffer of the trigger channel. If it is mutated,
+    /// remember to call [`update_match`](Self::update_match) afterwards.
     ///
     /// The length of the slice is `input_size` given on construction.
     pub fn get_buffer_mut(&mut self) -> &mut [f32] {
-        &mut self.buffer
+        &mut self.buffers[0]
     }
 
-    /// Update the correlation match position, memory buffer and period estimate based on newest data.
+    /// Get a mutable reference to the input buffer of `channel`, 0 being the trigger channel.
+    pub fn get_channel_buffer_mut(&mut self, channel: usize) -> &mut [f32] {
+        &mut self.buffers[channel]
+    }
+
+    /// Update the correlation match position, memory buffer and period estimate based on newest
+    /// data of the trigger channel.
     ///
     /// If `stabilize` is set to `false`, no matching is performed, and the previously set offset
     /// is retained.
@@ -115,15 +223,16 @@ impl DisplayBuffer {
     ///
     /// [`update_display`](Self::update_display) should be called separately to update the display buffer.
     pub fn update_match(&mut self, stabilize: bool, memory_decay: f32, period_decay: f32) {
+        let buffer = &self.buffers[0];
         if stabilize {
             let (offset, interval) =
                 self.correlation_matcher
-                    .compute(&self.buffer, &self.memory, &self.weight);
+                    .compute(buffer, &self.memory, &self.weight);
             let rounded = offset.round();
             self.offset = rounded as usize;
             self.residual += offset - rounded;
             self.offset = (self.offset as i64 + self.residual as i64)
-                .clamp(0, self.buffer.len() as i64 - 1) as usize;
+                .clamp(0, (buffer.len() - self.size) as i64) as usize;
             self.residual = self.residual.fract();
             if let Some(interval) = interval {
                 self.average_period =
@@ -131,25 +240,33 @@ impl DisplayBuffer {
             }
         }
         for (index, item) in self.memory.iter_mut().enumerate() {
-            *item = memory_decay * self.buffer[index + self.offset] + (1. - memory_decay) * *item;
+            *item = memory_decay * buffer[index + self.offset] + (1. - memory_decay) * *item;
         }
     }
 
-    /// Update the display buffer based on the newest input data and matched offset.
+    /// Update the display buffers of all channels based on the newest input data and matched
+    /// offset.
     ///
     /// This method may be called more often than [`update_match`](Self::update_match), even when
     /// there is no new data, to animate smoothly.
     pub fn update_display(&mut self, display_decay: f32) {
-        for (index, item) in self.display.iter_mut().enumerate() {
-            *item = display_decay * self.buffer[index + self.offset] + (1. - display_decay) * *item;
+        for (buffer, display) in self.buffers.iter().zip(self.displays.iter_mut()) {
+            for (index, item) in display.iter_mut().enumerate() {
+                *item = display_decay * buffer[index + self.offset] + (1. - display_decay) * *item;
+            }
         }
     }
 
-    /// Retrieve the contents of the display buffer.
+    /// Retrieve the contents of the display buffer of the trigger channel.
     ///
     /// The length of the slice is `display_size` given on construction.
     pub fn get_display(&self) -> &[f32] {
-        &self.display
+        &self.displays[0]
+    }
+
+    /// Retrieve the contents of the display buffer of `channel`, 0 being the trigger channel.
+    pub fn get_channel_display(&self, channel: usize) -> &[f32] {
+        &self.displays[channel]
     }
 
     /// Retrieve the contents of the memory buffer. This is what is used to find a
@@ -180,3 +297,33 @@ impl DisplayBuffer {
         (self.offset, self.residual)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_aligned_channels() {
+        let mut display = DisplayBuffer::with_channels(2, 512, 384, WeightWindow::Blackman);
+        for _ in 0..4 {
+            for (i, v) in display.get_buffer_mut().iter_mut().enumerate() {
+                *v = (i as f32 * 0.05).sin();
+            }
+            for (i, v) in display.get_channel_buffer_mut(1).iter_mut().enumerate() {
+                *v = -(i as f32 * 0.05).sin();
+            }
+            display.update_match(true, 1., 1.);
+        }
+        display.update_display(1.);
+        let (offset, _) = display.get_offset();
+        assert!(offset <= 512 - 384);
+        for (a, b) in display
+            .get_display()
+            .iter()
+            .zip(display.get_channel_display(1))
+        {
+            assert_eq!(*a, -*b);
+        }
+        display.set_channels(3);
+        assert_eq!(display.get_channel_display(2).len(), 384);
+    }
+}
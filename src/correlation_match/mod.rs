@@ -32,6 +32,7 @@ pub mod cross_correlation;
 pub mod display;
 pub mod fft;
 pub mod iter_windows;
+pub mod real_fft;
 pub mod ring_buffer;
 
 //use crate::cross_correlation::CrossCorrelation;
@@ -77,6 +78,9 @@ pub struct CorrelationMatch {
 }
 
 impl CorrelationMatch {
+    /// Number of calls to `compute_step` that make up one `compute`
+    pub const COMPUTE_STEPS: usize = 3;
+
     /// Allocate and prepare a correlation match algorithm. `max_size` is
     /// the maximum size of any of the input arrays.
     pub fn new(max_size: usize) -> Self {
@@ -107,6 +111,36 @@ impl CorrelationMatch {
         self.find_minimum_and_period()
     }
 
+    /// Same as `compute`, split into `COMPUTE_STEPS` calls so the work can be spread over time.
+    /// `step` counts up from 0 with the same arrays each time, the result is returned by the last
+    /// step. Each step does at most one cross correlation.
+    pub fn compute_step(
+        &mut self,
+        step: usize,
+        a: &[f32],
+        b: &[f32],
+        w: &[f32],
+    ) -> Option<(f32, Option<f32>)> {
+        match step {
+            0 => {
+                assert!(a.len() <= self.max_size);
+                assert!(b.len() <= a.len());
+                assert!(w.len() == b.len());
+                self.zero_buffers(a.len(), b.len());
+                self.compute_a_squared_term(a, w);
+                None
+            }
+            1 => {
+                self.compute_cross_term(a, b, w);
+                None
+            }
+            _ => {
+                self.compute_b_squared_term(b, w);
+                Some(self.find_minimum_and_period())
+            }
+        }
+    }
+
     fn zero_buffers(&mut self, a_len: usize, b_len: usize) {
         self.f_buffer.resize(a_len, 0.);
         self.g_buffer.resize(b_len, 0.);
@@ -0,0 +1,249 @@
+//! FFT of real signals, used by [`CrossCorrelation`](super::cross_correlation::CrossCorrelation).
+//!
+//! Complex values are kept in separate real and imaginary arrays, and the twiddle factors of each
+//! stage are stored one after the other, so the inner loops run over contiguous slices that the
+//! compiler can vectorize.
+
+use std::f32::consts::PI;
+
+/// Complex FFT on split real and imaginary arrays.
+pub struct SplitFft {
+    size: usize,
+    // Twiddles of the stage with half width `h` are at `h - 1..2 * h - 1`
+    twiddle_re: Vec<f32>,
+    twiddle_im: Vec<f32>,
+    // Index pairs swapped by the bit reversal permutation
+    swaps: Vec<(usize, usize)>,
+}
+
+impl SplitFft {
+    /// Prepare FFT. Size has to be a power of two.
+    pub fn new(size: usize) -> Self {
+        assert!(size.count_ones() == 1);
+        let mut twiddle_re = Vec::with_capacity(size);
+        let mut twiddle_im = Vec::with_capacity(size);
+        let mut half_width = 1;
+        while half_width < size {
+            for i in 0..half_width {
+                let angle = -(i as f32) * PI / half_width as f32;
+                twiddle_re.push(angle.cos());
+                twiddle_im.push(angle.sin());
+            }
+            half_width *= 2;
+        }
+        let bits = size.trailing_zeros();
+        let swaps = (0..size)
+            .filter_map(|index| {
+                let reversed = index.reverse_bits().checked_shr(usize::BITS - bits)?;
+                if reversed > index {
+                    Some((index, reversed))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        SplitFft {
+            size,
+            twiddle_re,
+            twiddle_im,
+            swaps,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Perform the transform in place. Both arrays have to be the size this instance was
+    /// prepared with.
+    pub fn fft(&self, re: &mut [f32], im: &mut [f32]) {
+        assert!(re.len() == self.size && im.len() == self.size);
+        for &(a, b) in self.swaps.iter() {
+            re.swap(a, b);
+            im.swap(a, b);
+        }
+        let mut half_width = 1;
+        if self.size >= 4 {
+            // The first two stages only use the twiddles 1 and -i, so they're done together
+            // without multiplications
+            for (re, im) in re.chunks_exact_mut(4).zip(im.chunks_exact_mut(4)) {
+                let (a_re, a_im) = (re[0] + re[1], im[0] + im[1]);
+                let (b_re, b_im) = (re[0] - re[1], im[0] - im[1]);
+                let (c_re, c_im) = (re[2] + re[3], im[2] + im[3]);
+                let (d_re, d_im) = (re[2] - re[3], im[2] - im[3]);
+                re[0] = a_re + c_re;
+                im[0] = a_im + c_im;
+                re[2] = a_re - c_re;
+                im[2] = a_im - c_im;
+                // d * -i
+                re[1] = b_re + d_im;
+                im[1] = b_im - d_re;
+                re[3] = b_re - d_im;
+                im[3] = b_im + d_re;
+            }
+            half_width = 4;
+        }
+        while half_width < self.size {
+            let twiddle_re = &self.twiddle_re[half_width - 1..2 * half_width - 1];
+            let twiddle_im = &self.twiddle_im[half_width - 1..2 * half_width - 1];
+            for (re, im) in re
+                .chunks_exact_mut(2 * half_width)
+                .zip(im.chunks_exact_mut(2 * half_width))
+            {
+                let (l_re, r_re) = re.split_at_mut(half_width);
+                let (l_im, r_im) = im.split_at_mut(half_width);
+                let left = l_re.iter_mut().zip(l_im.iter_mut());
+                let right = r_re.iter_mut().zip(r_im.iter_mut());
+                let twiddle = twiddle_re.iter().zip(twiddle_im.iter());
+                for ((l_re, l_im), ((r_re, r_im), (w_re, w_im))) in left.zip(right.zip(twiddle)) {
+                    let t_re = *r_re * w_re - *r_im * w_im;
+                    let t_im = *r_re * w_im + *r_im * w_re;
+                    *r_re = *l_re - t_re;
+                    *r_im = *l_im - t_im;
+                    *l_re += t_re;
+                    *l_im += t_im;
+                }
+            }
+            half_width *= 2;
+        }
+    }
+
+    /// Perform the inverse transform in place, including the `1 / size` normalization.
+    pub fn ifft(&self, re: &mut [f32], im: &mut [f32]) {
+        // ifft(z) = conj(fft(conj(z))) / size
+        im.iter_mut().for_each(|v| *v = -*v);
+        self.fft(re, im);
+        let scale = 1. / self.size as f32;
+        re.iter_mut().for_each(|v| *v *= scale);
+        im.iter_mut().for_each(|v| *v *= -scale);
+    }
+}
+
+/// FFT of a real signal of `size` samples, computed with a complex FFT of half the size.
+///
+/// Only bins `0..=size / 2` of the spectrum are stored, the rest are their complex conjugates.
+pub struct RealFft {
+    size: usize,
+    fft: SplitFft,
+    // e^(-2 pi i k / size) for k in 0..=size / 2
+    twiddle_re: Vec<f32>,
+    twiddle_im: Vec<f32>,
+    z_re: Vec<f32>,
+    z_im: Vec<f32>,
+}
+
+impl RealFft {
+    /// Prepare FFT. Size has to be a power of two, at least 4.
+    pub fn new(size: usize) -> Self {
+        assert!(size.count_ones() == 1 && size >= 4);
+        let half_size = size / 2;
+        let (twiddle_re, twiddle_im) = (0..=half_size)
+            .map(|k| {
+                let angle = -2. * PI * k as f32 / size as f32;
+                (angle.cos(), angle.sin())
+            })
+            .unzip();
+        RealFft {
+            size,
+            fft: SplitFft::new(half_size),
+            twiddle_re,
+            twiddle_im,
+            z_re: vec![0.; half_size],
+            z_im: vec![0.; half_size],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Number of bins in the spectrum, `size / 2 + 1`
+    pub fn spectrum_size(&self) -> usize {
+        self.size / 2 + 1
+    }
+
+    /// Transform `input` of `size` samples into bins `0..=size / 2` of its spectrum.
+    pub fn forward(&mut self, input: &[f32], out_re: &mut [f32], out_im: &mut [f32]) {
+        assert!(input.len() == self.size);
+        assert!(out_re.len() == self.spectrum_size() && out_im.len() == self.spectrum_size());
+        let half_size = self.size / 2;
+        // Even samples go in the real part and odd ones in the imaginary part, z = e + i * o
+        for (pair, (z_re, z_im)) in input
+            .chunks_exact(2)
+            .zip(self.z_re.iter_mut().zip(self.z_im.iter_mut()))
+        {
+            *z_re = pair[0];
+            *z_im = pair[1];
+        }
+        self.fft.fft(&mut self.z_re, &mut self.z_im);
+        for k in 0..=half_size {
+            let (z_re, z_im) = (self.z_re[k % half_size], self.z_im[k % half_size]);
+            let mk = (half_size - k) % half_size;
+            let (zm_re, zm_im) = (self.z_re[mk], -self.z_im[mk]);
+            // e[k] = (z[k] + conj(z[-k])) / 2, o[k] = (z[k] - conj(z[-k])) / 2i
+            let (e_re, e_im) = (0.5 * (z_re + zm_re), 0.5 * (z_im + zm_im));
+            let (o_re, o_im) = (0.5 * (z_im - zm_im), -0.5 * (z_re - zm_re));
+            // x[k] = e[k] + w^k * o[k]
+            let (w_re, w_im) = (self.twiddle_re[k], self.twiddle_im[k]);
+            out_re[k] = e_re + w_re * o_re - w_im * o_im;
+            out_im[k] = e_im + w_re * o_im + w_im * o_re;
+        }
+    }
+
+    /// Transform bins `0..=size / 2` of a spectrum back into `size` samples.
+    pub fn inverse(&mut self, in_re: &[f32], in_im: &[f32], output: &mut [f32]) {
+        assert!(in_re.len() == self.spectrum_size() && in_im.len() == self.spectrum_size());
+        assert!(output.len() == self.size);
+        let half_size = self.size / 2;
+        for k in 0..half_size {
+            let (x_re, x_im) = (in_re[k], in_im[k]);
+            let (xm_re, xm_im) = (in_re[half_size - k], -in_im[half_size - k]);
+            // e[k] = (x[k] + conj(x[n/2 - k])) / 2, o[k] = (x[k] - conj(x[n/2 - k])) / 2 * w^-k
+            let (e_re, e_im) = (0.5 * (x_re + xm_re), 0.5 * (x_im + xm_im));
+            let (d_re, d_im) = (0.5 * (x_re - xm_re), 0.5 * (x_im - xm_im));
+            let (w_re, w_im) = (self.twiddle_re[k], -self.twiddle_im[k]);
+            let (o_re, o_im) = (d_re * w_re - d_im * w_im, d_re * w_im + d_im * w_re);
+            // z[k] = e[k] + i * o[k]
+            self.z_re[k] = e_re - o_im;
+            self.z_im[k] = e_im + o_re;
+        }
+        self.fft.ifft(&mut self.z_re, &mut self.z_im);
+        for (pair, (z_re, z_im)) in output
+            .chunks_exact_mut(2)
+            .zip(self.z_re.iter().zip(self.z_im.iter()))
+        {
+            pair[0] = *z_re;
+            pair[1] = *z_im;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::correlation_match::{complex::Complex, fft::Fft};
+    #[test]
+    fn test_real_fft() {
+        let size = 64;
+        let input: Vec<f32> = (0..size)
+            .map(|i| (i as f32 * 0.3).sin() + 0.5 * (i as f32 * 1.7).cos() + 0.1)
+            .collect();
+        let mut expected: Vec<Complex> = input.iter().map(|v| (*v, 0.).into()).collect();
+        Fft::new(size).fft(&mut expected);
+
+        let mut real_fft = RealFft::new(size);
+        let mut re = vec![0.; real_fft.spectrum_size()];
+        let mut im = vec![0.; real_fft.spectrum_size()];
+        real_fft.forward(&input, &mut re, &mut im);
+        for k in 0..=size / 2 {
+            assert!((re[k] - expected[k].real).abs() < 1e-3);
+            assert!((im[k] - expected[k].imag).abs() < 1e-3);
+        }
+
+        let mut output = vec![0.; size];
+        real_fft.inverse(&re, &im, &mut output);
+        for (a, b) in input.iter().zip(output.iter()) {
+            assert!((a - b).abs() < 1e-5);
+        }
+    }
+}
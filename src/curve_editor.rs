@@ -0,0 +1,296 @@
+use egui::{pos2, vec2, Color32, Pos2, Response, Sense, Stroke, Ui};
+
+// Must match the size of the arrays in the `Curve` struct declared in `compiler::compile`
+pub const CURVE_MAX_POINTS: usize = 32;
+
+const POINT_RADIUS: f32 = 6.0;
+const SEGMENT_STEPS: usize = 24;
+
+/// Breakpoint curve shared between the editor and process JITs.
+///
+/// Both axes are normalized to 0..1 and points are kept sorted by `x`.
+/// `tension[i]` bends the segment that starts at point `i`, 0.0 is a straight line.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Curve {
+    pub x: [f32; CURVE_MAX_POINTS],
+    pub y: [f32; CURVE_MAX_POINTS],
+    pub tension: [f32; CURVE_MAX_POINTS],
+    pub len: i64,
+}
+
+impl Default for Curve {
+    fn default() -> Self {
+        let mut curve = Curve {
+            x: [0.0; CURVE_MAX_POINTS],
+            y: [0.0; CURVE_MAX_POINTS],
+            tension: [0.0; CURVE_MAX_POINTS],
+            len: 2,
+        };
+        curve.x[1] = 1.0;
+        curve.y[1] = 1.0;
+        curve
+    }
+}
+
+/// Bends `t` (0..1) towards either end, `tension` is clamped to -1..1.
+fn shape(t: f32, tension: f32) -> f32 {
+    t.powf((tension.clamp(-1.0, 1.0) * 3.0).exp2())
+}
+
+impl Curve {
+    pub fn points(&self) -> usize {
+        (self.len.max(0) as usize).min(CURVE_MAX_POINTS)
+    }
+
+    /// Evaluate the curve at `x`. Values outside of the first and last point are held.
+    pub fn eval(&self, x: f32) -> f32 {
+        let len = self.points();
+        if len == 0 {
+            return 0.0;
+        }
+        if x <= self.x[0] {
+            return self.y[0];
+        }
+        for i in 1..len {
+            if x <= self.x[i] {
+                let width = self.x[i] - self.x[i - 1];
+                let t = if width > 0.0 {
+                    (x - self.x[i - 1]) / width
+                } else {
+                    1.0
+                };
+                return self.y[i - 1] + (self.y[i] - self.y[i - 1]) * shape(t, self.tension[i - 1]);
+            }
+        }
+        self.y[len - 1]
+    }
+
+    /// Insert a point keeping the points sorted. Returns the index of the new point.
+    pub fn insert(&mut self, x: f32, y: f32) -> Option<usize> {
+        let len = self.points();
+        if len >= CURVE_MAX_POINTS {
+            return None;
+        }
+        let index = (0..len).find(|i| self.x[*i] > x).unwrap_or(len);
+        for i in (index..len).rev() {
+            self.x[i + 1] = self.x[i];
+            self.y[i + 1] = self.y[i];
+            self.tension[i + 1] = self.tension[i];
+        }
+        self.x[index] = x;
+        self.y[index] = y;
+        self.tension[index] = if index > 0 {
+            self.tension[index - 1]
+        } else {
+            0.0
+        };
+        self.len = len as i64 + 1;
+        Some(index)
+    }
+
+    /// Remove the point at `index`. The last two points can't be removed.
+    pub fn remove(&mut self, index: usize) {
+        let len = self.points();
+        if len <= 2 || index >= len {
+            return;
+        }
+        for i in index..len - 1 {
+            self.x[i] = self.x[i + 1];
+            self.y[i] = self.y[i + 1];
+            self.tension[i] = self.tension[i + 1];
+        }
+        self.len = len as i64 - 1;
+    }
+
+    /// Move the point at `index`, `x` is limited by the neighbouring points.
+    pub fn move_point(&mut self, index: usize, x: f32, y: f32) {
+        let len = self.points();
+        if index >= len {
+            return;
+        }
+        let min_x = if index > 0 { self.x[index - 1] } else { 0.0 };
+        let max_x = if index + 1 < len {
+            self.x[index + 1]
+        } else {
+            1.0
+        };
+        self.x[index] = x.clamp(min_x, max_x);
+        self.y[index] = y.clamp(0.0, 1.0);
+    }
+
+    /// Index of the segment (the point it starts at) containing `x`.
+    pub fn segment_at(&self, x: f32) -> Option<usize> {
+        let len = self.points();
+        (1..len).find(|i| x <= self.x[*i]).map(|i| i - 1)
+    }
+
+    /// Fix up curves that were not initialized with at least two sorted points.
+    pub fn sanitize(&mut self) {
+        if self.len < 2 || self.len > CURVE_MAX_POINTS as i64 {
+            *self = Curve::default();
+            return;
+        }
+        for i in 1..self.points() {
+            if self.x[i] < self.x[i - 1] {
+                self.x[i] = self.x[i - 1];
+            }
+        }
+    }
+}
+
+/// Interactive editor for a [`Curve`].
+///
+/// Drag points to move them, double click to add a point, right click a point to delete it
+/// and ctrl drag a segment up or down to change its tension.
+pub fn curve_editor_ui(ui: &mut Ui, curve: &mut Curve) -> Response {
+    curve.sanitize();
+    let width = ui.available_width().min(400.0);
+    let (mut response, painter) =
+        ui.allocate_painter(vec2(width, width * 0.6), Sense::click_and_drag());
+    let rect = response.rect;
+    let to_screen = |x: f32, y: f32| -> Pos2 {
+        pos2(
+            rect.left() + x * rect.width(),
+            rect.bottom() - y * rect.height(),
+        )
+    };
+    let from_screen = |p: Pos2| -> (f32, f32) {
+        (
+            ((p.x - rect.left()) / rect.width()).clamp(0.0, 1.0),
+            ((rect.bottom() - p.y) / rect.height()).clamp(0.0, 1.0),
+        )
+    };
+    let point_at = |curve: &Curve, p: Pos2| -> Option<usize> {
+        (0..curve.points())
+            .find(|i| to_screen(curve.x[*i], curve.y[*i]).distance(p) <= POINT_RADIUS * 2.0)
+    };
+
+    let drag_id = response.id.with("dragged_point");
+    let mut changed = false;
+
+    if response.drag_started() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            let target = if ui.input().modifiers.ctrl {
+                curve
+                    .segment_at(from_screen(pos).0)
+                    .map(DragTarget::Tension)
+            } else {
+                point_at(curve, pos).map(DragTarget::Point)
+            };
+            if let Some(target) = target {
+                ui.memory().id_data_temp.insert(drag_id, target);
+            }
+        }
+    }
+    if response.dragged() {
+        let target = ui
+            .memory()
+            .id_data_temp
+            .get::<DragTarget>(&drag_id)
+            .copied();
+        match target {
+            Some(DragTarget::Point(i)) => {
+                if let Some(pos) = response.interact_pointer_pos() {
+                    let (x, y) = from_screen(pos);
+                    curve.move_point(i, x, y);
+                    changed = true;
+                }
+            }
+            Some(DragTarget::Tension(i)) => {
+                let delta = response.drag_delta().y / rect.height();
+                let falling = curve.y[i + 1] < curve.y[i];
+                let delta = if falling { -delta } else { delta };
+                curve.tension[i] = (curve.tension[i] + delta * 2.0).clamp(-1.0, 1.0);
+                changed = true;
+            }
+            None => (),
+        }
+    }
+    if response.drag_released() {
+        ui.memory().id_data_temp.remove(&drag_id);
+    }
+    if response.double_clicked() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            if point_at(curve, pos).is_none() {
+                let (x, y) = from_screen(pos);
+                changed |= curve.insert(x, y).is_some();
+            }
+        }
+    }
+    if response.secondary_clicked() {
+        if let Some(pos) = response.interact_pointer_pos() {
+            if let Some(i) = point_at(curve, pos) {
+                curve.remove(i);
+                changed = true;
+            }
+        }
+    }
+
+    let visuals = ui.style().visuals.clone();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    let grid_stroke = Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color);
+    for i in 1..4 {
+        let f = i as f32 / 4.0;
+        painter.line_segment([to_screen(f, 0.0), to_screen(f, 1.0)], grid_stroke);
+        painter.line_segment([to_screen(0.0, f), to_screen(1.0, f)], grid_stroke);
+    }
+
+    let line_stroke = Stroke::new(2.0, visuals.selection.bg_fill);
+    let len = curve.points();
+    let mut last = to_screen(0.0, curve.y[0]);
+    for i in 0..len {
+        let steps = if i == 0 { 1 } else { SEGMENT_STEPS };
+        let x0 = if i == 0 { 0.0 } else { curve.x[i - 1] };
+        for step in 1..=steps {
+            let x = x0 + (curve.x[i] - x0) * step as f32 / steps as f32;
+            let p = to_screen(x, curve.eval(x));
+            painter.line_segment([last, p], line_stroke);
+            last = p;
+        }
+    }
+    painter.line_segment([last, to_screen(1.0, curve.y[len - 1])], line_stroke);
+
+    let hovered = response.hover_pos().and_then(|p| point_at(curve, p));
+    for i in 0..len {
+        let color = if hovered == Some(i) {
+            visuals.widgets.hovered.fg_stroke.color
+        } else {
+            visuals.widgets.inactive.fg_stroke.color
+        };
+        painter.circle_filled(to_screen(curve.x[i], curve.y[i]), POINT_RADIUS, color);
+    }
+    painter.rect_stroke(rect, 0.0, Stroke::new(1.0, Color32::from_gray(90)));
+
+    if changed {
+        response.mark_changed();
+    }
+    response
+}
+
+#[derive(Clone, Copy)]
+enum DragTarget {
+    Point(usize),
+    Tension(usize),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_curve_edit_and_eval() {
+        let mut curve = Curve::default();
+        assert_eq!(curve.eval(0.5), 0.5);
+        assert_eq!(curve.insert(0.5, 1.0), Some(1));
+        assert_eq!(curve.points(), 3);
+        assert_eq!(curve.eval(0.25), 0.5);
+        assert_eq!(curve.eval(0.75), 1.0);
+        curve.tension[0] = 1.0;
+        assert!(curve.eval(0.25) < 0.5);
+        curve.move_point(1, 2.0, 0.0);
+        assert_eq!(curve.x[1], 1.0);
+        curve.remove(1);
+        curve.remove(1);
+        assert_eq!(curve.points(), 2);
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    correlation_match::display::WeightWindow,
+    debugger::{ChannelView, DebugChannel, DEFAULT_CHANNEL_SIZE},
+    scope::TriggerMode,
+};
+
+/// View settings of a debugger channel, matched to channels by name when they're probed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct ChannelLayout {
+    pub name: String,
+    pub visible: bool,
+    pub view: ChannelView,
+    pub show_pitch: bool,
+    pub display_decay: f32,
+    pub memory_decay: f32,
+    pub enable_waveform: bool,
+    pub enable_smoothing: bool,
+    pub weight_window: WeightWindow,
+    pub display_size: usize,
+    pub match_interval: usize,
+    /// Channels lined up with this one in the waveform view
+    pub aligned: Vec<String>,
+    pub scope_mode: TriggerMode,
+    pub scope_level: f32,
+    pub scope_time_base: usize,
+    pub scope_scale: f32,
+    pub spectrum_averaging: f32,
+    pub spectrum_peak_hold: bool,
+}
+
+impl Default for ChannelLayout {
+    fn default() -> Self {
+        ChannelLayout {
+            name: String::new(),
+            visible: true,
+            view: ChannelView::Waveform,
+            show_pitch: false,
+            display_decay: 0.6,
+            memory_decay: 0.8,
+            enable_waveform: true,
+            enable_smoothing: false,
+            weight_window: WeightWindow::Hann,
+            display_size: DEFAULT_CHANNEL_SIZE * 3 / 4,
+            match_interval: 0,
+            aligned: Vec::new(),
+            scope_mode: TriggerMode::Rising,
+            scope_level: 0.0,
+            scope_time_base: 512,
+            scope_scale: 1.0,
+            spectrum_averaging: 0.7,
+            spectrum_peak_hold: false,
+        }
+    }
+}
+
+impl ChannelLayout {
+    pub fn from_channel(channel: &DebugChannel) -> Self {
+        ChannelLayout {
+            name: channel.name.clone(),
+            visible: channel.visible,
+            view: channel.view,
+            show_pitch: channel.show_pitch,
+            display_decay: channel.waveform.display_decay,
+            memory_decay: channel.waveform.memory_decay,
+            enable_waveform: channel.waveform.enable_waveform,
+            enable_smoothing: channel.waveform.enable_smoothing,
+            weight_window: channel.waveform.window,
+            display_size: channel.waveform.display_size,
+            match_interval: channel.waveform.match_interval,
+            aligned: channel.waveform.aligned.clone(),
+            scope_mode: channel.scope.mode,
+            scope_level: channel.scope.level,
+            scope_time_base: channel.scope.time_base,
+            scope_scale: channel.scope.vertical_scale,
+            spectrum_averaging: channel.spectrum.averaging,
+            spectrum_peak_hold: channel.spectrum.peak_hold,
+        }
+    }
+
+    pub fn apply(&self, channel: &mut DebugChannel) {
+        channel.visible = self.visible;
+        channel.view = self.view;
+        channel.show_pitch = self.show_pitch;
+        channel.waveform.display_decay = self.display_decay;
+        channel.waveform.memory_decay = self.memory_decay;
+        channel.waveform.enable_waveform = self.enable_waveform;
+        channel.waveform.enable_smoothing = self.enable_smoothing;
+        channel.waveform.window = self.weight_window;
+        channel.waveform.display_size = self.display_size;
+        channel.waveform.match_interval = self.match_interval;
+        channel.waveform.aligned = self.aligned.clone();
+        channel.scope.mode = self.scope_mode;
+        channel.scope.level = self.scope_level;
+        channel.scope.time_base = self.scope_time_base;
+        channel.scope.vertical_scale = self.scope_scale;
+        channel.spectrum.averaging = self.spectrum_averaging.clamp(0.0, 0.99);
+        channel.spectrum.peak_hold = self.spectrum_peak_hold;
+    }
+}
+
+/// Debug panel settings, stored per project in `Config::debug_layouts`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct DebugLayout {
+    pub io_taps: bool,
+    /// Names of the channels plotted against each other in the XY view
+    pub xy_channels: [String; 2],
+    // Tables have to come after the plain values for the config to serialize to toml
+    pub channels: Vec<ChannelLayout>,
+}
+
+impl Default for DebugLayout {
+    fn default() -> Self {
+        DebugLayout {
+            io_taps: false,
+            xy_channels: ["0".to_string(), "1".to_string()],
+            channels: Vec::new(),
+        }
+    }
+}
+
+impl DebugLayout {
+    pub fn channel(&self, name: &str) -> Option<&ChannelLayout> {
+        self.channels.iter().find(|channel| channel.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_layout_toml() {
+        let layout = DebugLayout {
+            io_taps: true,
+            xy_channels: ["env".to_string(), "3".to_string()],
+            channels: vec![ChannelLayout {
+                name: "env".to_string(),
+                visible: false,
+                view: ChannelView::Spectrum,
+                scope_mode: TriggerMode::Falling,
+                ..Default::default()
+            }],
+        };
+        let text = toml::to_string(&layout).unwrap();
+        assert_eq!(toml::from_str::<DebugLayout>(&text).unwrap(), layout);
+        let partial: DebugLayout = toml::from_str("[[channels]]\nname = \"a\"\n").unwrap();
+        assert_eq!(partial.xy_channels, ["0".to_string(), "1".to_string()]);
+        assert!(partial.channel("a").unwrap().visible);
+    }
+}
@@ -0,0 +1,954 @@
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc,
+};
+
+use ringbuf::{Consumer, Producer, RingBuffer};
+use serde::{Deserialize, Serialize};
+use triple_buffer::{Input, Output, TripleBuffer};
+
+use crate::{
+    atomic_f32::AtomicF32,
+    compiler_interface::WaveformDisplay,
+    console::{ConsoleInput, ConsoleOutput, LogSource, LogValue},
+    debug_layout::{ChannelLayout, DebugLayout},
+    freeze::{FreezeControl, TriggerReason},
+    pitch::PitchDetector,
+    scope::Scope,
+    spectrum::SpectrumAnalyzer,
+    stats::ChannelStats,
+    units::ConsumerRingBuf,
+};
+
+pub const MAX_DEBUG_CHANNELS: usize = 64;
+pub const DEFAULT_CHANNEL_SIZE: usize = 1024;
+pub const MIN_CHANNEL_SIZE: usize = 16;
+pub const MAX_CHANNEL_SIZE: usize = 1 << 18;
+// Channels 0..DEFAULT_CHANNELS exist from the start so `dbg.show(0, x)` works without a probe
+pub const DEFAULT_CHANNELS: usize = 4;
+const CHANNEL_NAME_LEN: usize = 32;
+// Number of arrays a vector channel can buffer before the editor picks them up
+const VECTOR_FRAMES: usize = 4;
+/// Largest magnitude of integer probes, the table marks values that were clamped to it
+pub const MAX_EXACT_INT: i64 = 1 << 24;
+/// Number of frames of plugin input and output kept for the goniometer
+pub const STEREO_TAP_SIZE: usize = 2048;
+/// Channels the plugin input and output are sent to while the I/O taps are enabled
+pub const IO_TAP_CHANNELS: [&str; 4] = ["input L", "input R", "output L", "output R"];
+
+/// What a channel carries, which decides how it's displayed in the debug panel
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProbeKind {
+    /// One value per sample, shown as a waveform
+    Samples,
+    /// One value per block, shown as a table
+    Block,
+    /// Integers, shown as a table. Values are sent as f32, so they're clamped to
+    /// `±MAX_EXACT_INT` where they're still exact.
+    Integer,
+    /// On/off values, shown as a strip
+    Bool,
+    /// A whole array per block, the latest one is shown as a bar chart
+    Vector,
+}
+
+/// How a sample channel is displayed
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum ChannelView {
+    Waveform,
+    Scope,
+    Spectrum,
+}
+
+/// Fixed size channel name so channels can be registered on the audio thread without allocating
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct ChannelName {
+    bytes: [u8; CHANNEL_NAME_LEN],
+    len: usize,
+}
+
+impl ChannelName {
+    /// Names longer than `CHANNEL_NAME_LEN` bytes are truncated
+    pub(crate) fn new(name: &str) -> Self {
+        let mut len = name.len().min(CHANNEL_NAME_LEN);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        let mut bytes = [0; CHANNEL_NAME_LEN];
+        bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+        ChannelName { bytes, len }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap_or("")
+    }
+}
+
+/// Sent from the audio thread when a script probes a channel that doesn't exist yet
+struct ChannelRequest {
+    generation: usize,
+    index: usize,
+    name: ChannelName,
+    kind: ProbeKind,
+    size: usize,
+}
+
+/// Sent back from the editor once the buffer for a requested channel has been allocated
+struct NewChannel {
+    generation: usize,
+    index: usize,
+    producer: Producer<f32>,
+    stats: StatsIn,
+}
+
+/// Sent from the editor to have the values of a channel copied to a recording
+struct RecordTap {
+    generation: usize,
+    index: usize,
+    record: RecordIn,
+}
+
+/// Where the audio thread copies the values of a channel that's being recorded
+struct RecordIn {
+    producer: Producer<f32>,
+    // Values that didn't fit, so the recording has gaps
+    dropped: Arc<AtomicUsize>,
+}
+
+/// Buffers the audio thread is done with, sent back to the editor to be freed there
+#[derive(Default)]
+struct FreedChannel {
+    producer: Option<Producer<f32>>,
+    stats: Option<StatsIn>,
+    record: Option<RecordIn>,
+}
+
+/// Where the audio thread publishes the stats of a channel
+struct StatsIn {
+    input: Input<ChannelStats>,
+    reset: Arc<AtomicBool>,
+}
+
+struct DebugChannelIn {
+    name: ChannelName,
+    kind: ProbeKind,
+    // Length of the arrays of vector channels, ignored otherwise
+    size: usize,
+    // None until the editor has allocated the buffer
+    producer: Option<Producer<f32>>,
+    stats: ChannelStats,
+    stats_in: Option<StatsIn>,
+    // Set while the channel is recorded
+    record: Option<RecordIn>,
+    // Values still taken after a trigger
+    post_remaining: usize,
+}
+
+impl DebugChannelIn {
+    fn new(name: ChannelName, kind: ProbeKind, size: usize) -> Self {
+        DebugChannelIn {
+            name,
+            kind,
+            size,
+            producer: None,
+            stats: ChannelStats::default(),
+            stats_in: None,
+            record: None,
+            post_remaining: 0,
+        }
+    }
+
+    /// Whether `n` more values can be sent, counting them against the post trigger history
+    fn take(&mut self, n: usize, triggered: bool) -> bool {
+        if !triggered {
+            return true;
+        }
+        if self.post_remaining < n {
+            return false;
+        }
+        self.post_remaining -= n;
+        true
+    }
+}
+
+/// Process side of the debugger, passed to Sarus as `Debugger`.
+///
+/// Channels are registered by name with `probe`. The buffers are allocated by the code editor,
+/// so values sent to a new channel are dropped until the editor has picked up the request.
+pub struct DebuggerInput {
+    channels: Vec<DebugChannelIn>,
+    generation: usize,
+    shared_generation: Arc<AtomicUsize>,
+    sample_rate: Arc<AtomicF32>,
+    requests: Producer<ChannelRequest>,
+    new_channels: Consumer<NewChannel>,
+    freed: Producer<FreedChannel>,
+    record_taps: Consumer<RecordTap>,
+    stop_recording: Arc<AtomicBool>,
+    stereo_taps: Arc<AtomicBool>,
+    io_taps: Arc<AtomicBool>,
+    console: ConsoleInput,
+    freeze: Arc<FreezeControl>,
+    // Local copy of the trigger state, only changes at the start of a block or on a trigger
+    triggered: bool,
+    input_tap: Producer<(f32, f32)>,
+    output_tap: Producer<(f32, f32)>,
+}
+
+/// Editor side of a debugger channel
+pub struct DebugChannel {
+    pub name: String,
+    pub kind: ProbeKind,
+    /// For vector channels this holds the latest array
+    pub data: ConsumerRingBuf<f32>,
+    /// Number of values that arrived in the last update
+    pub new_values: usize,
+    /// Hidden channels only show their name in the debug panel
+    pub visible: bool,
+    pub view: ChannelView,
+    pub waveform: WaveformDisplay,
+    pub scope: Scope,
+    pub spectrum: SpectrumAnalyzer,
+    pub show_pitch: bool,
+    /// Created when the pitch is first shown
+    pub pitch: Option<PitchDetector>,
+    stats: Output<ChannelStats>,
+    reset_stats: Arc<AtomicBool>,
+}
+
+impl DebugChannel {
+    fn new(
+        name: String,
+        kind: ProbeKind,
+        consumer: Consumer<f32>,
+        size: usize,
+        stats: Output<ChannelStats>,
+        reset_stats: Arc<AtomicBool>,
+    ) -> Self {
+        DebugChannel {
+            name,
+            kind,
+            data: ConsumerRingBuf::new(consumer, size),
+            new_values: 0,
+            visible: true,
+            view: ChannelView::Waveform,
+            waveform: WaveformDisplay::new(size),
+            scope: Scope::default(),
+            spectrum: SpectrumAnalyzer::new(size),
+            show_pitch: false,
+            pitch: None,
+            stats,
+            reset_stats,
+        }
+    }
+
+    /// Latest stats published by the audio thread
+    pub fn stats(&mut self) -> ChannelStats {
+        *self.stats.read()
+    }
+
+    /// Stats restart from the next block
+    pub fn reset_stats(&self) {
+        self.reset_stats.store(true, Ordering::Relaxed);
+    }
+
+    fn consume(&mut self) {
+        self.new_values = if self.kind == ProbeKind::Vector {
+            self.data.consume_frames(self.data.data.len())
+        } else {
+            self.data.consume()
+        };
+    }
+}
+
+fn new_stats() -> (StatsIn, Output<ChannelStats>, Arc<AtomicBool>) {
+    let (input, output) = TripleBuffer::new(ChannelStats::default()).split();
+    let reset = Arc::new(AtomicBool::new(false));
+    (
+        StatsIn {
+            input,
+            reset: reset.clone(),
+        },
+        output,
+        reset,
+    )
+}
+
+/// Size of the ring buffer between the threads for a channel showing `size` values
+fn ring_size(kind: ProbeKind, size: usize) -> usize {
+    match kind {
+        ProbeKind::Vector => size * VECTOR_FRAMES,
+        _ => size,
+    }
+}
+
+/// Editor side of the debugger.
+pub struct DebuggerOutput {
+    // Indexed the same as the channels of `DebuggerInput`, None while a request is in flight
+    pub channels: Vec<Option<DebugChannel>>,
+    generation: usize,
+    shared_generation: Arc<AtomicUsize>,
+    sample_rate: Arc<AtomicF32>,
+    requests: Consumer<ChannelRequest>,
+    new_channels: Producer<NewChannel>,
+    freed: Consumer<FreedChannel>,
+    record_taps: Producer<RecordTap>,
+    stop_recording: Arc<AtomicBool>,
+    stereo_taps: Arc<AtomicBool>,
+    io_taps: Arc<AtomicBool>,
+    /// Left and right of the plugin input, while the stereo taps are enabled
+    pub input_tap: ConsumerRingBuf<(f32, f32)>,
+    /// Left and right of the plugin output, while the stereo taps are enabled
+    pub output_tap: ConsumerRingBuf<(f32, f32)>,
+    /// Channels plotted against each other in the XY view, None until the channel of the saved
+    /// name is probed
+    pub xy_channels: [Option<usize>; 2],
+    /// Messages printed by the scripts
+    pub console: ConsoleOutput,
+    pub freeze: Arc<FreezeControl>,
+    // Settings of the project, applied to channels as they're created
+    layout: DebugLayout,
+}
+
+pub fn new_debugger() -> (DebuggerInput, DebuggerOutput) {
+    let (requests_in, requests_out) = RingBuffer::new(MAX_DEBUG_CHANNELS).split();
+    let (new_channels_in, new_channels_out) = RingBuffer::new(MAX_DEBUG_CHANNELS).split();
+    // Room for every probed channel of a generation and every new channel in flight
+    let (freed_in, freed_out) = RingBuffer::new(MAX_DEBUG_CHANNELS * 2).split();
+    let (record_taps_in, record_taps_out) = RingBuffer::new(MAX_DEBUG_CHANNELS).split();
+    let stop_recording = Arc::new(AtomicBool::new(false));
+    let shared_generation = Arc::new(AtomicUsize::new(0));
+    let sample_rate = Arc::new(AtomicF32::new(44100.0));
+    let stereo_taps = Arc::new(AtomicBool::new(false));
+    let io_taps = Arc::new(AtomicBool::new(false));
+    let mut console = ConsoleOutput::default();
+    let console_in = console.new_input(LogSource::Process);
+    let freeze = Arc::new(FreezeControl::default());
+    let (input_tap_in, input_tap_out) = RingBuffer::new(STEREO_TAP_SIZE).split();
+    let (output_tap_in, output_tap_out) = RingBuffer::new(STEREO_TAP_SIZE).split();
+    let mut channels_in = Vec::with_capacity(MAX_DEBUG_CHANNELS);
+    let mut channels_out = Vec::with_capacity(MAX_DEBUG_CHANNELS);
+    for i in 0..DEFAULT_CHANNELS {
+        let (prod, cons) = RingBuffer::<f32>::new(DEFAULT_CHANNEL_SIZE).split();
+        let (stats_in, stats_out, reset_stats) = new_stats();
+        let mut channel = DebugChannelIn::new(
+            ChannelName::new(&i.to_string()),
+            ProbeKind::Samples,
+            DEFAULT_CHANNEL_SIZE,
+        );
+        channel.producer = Some(prod);
+        channel.stats_in = Some(stats_in);
+        channels_in.push(channel);
+        channels_out.push(Some(DebugChannel::new(
+            i.to_string(),
+            ProbeKind::Samples,
+            cons,
+            DEFAULT_CHANNEL_SIZE,
+            stats_out,
+            reset_stats,
+        )));
+    }
+    (
+        DebuggerInput {
+            channels: channels_in,
+            generation: 0,
+            shared_generation: shared_generation.clone(),
+            sample_rate: sample_rate.clone(),
+            requests: requests_in,
+            new_channels: new_channels_out,
+            freed: freed_in,
+            record_taps: record_taps_out,
+            stop_recording: stop_recording.clone(),
+            stereo_taps: stereo_taps.clone(),
+            io_taps: io_taps.clone(),
+            console: console_in,
+            freeze: freeze.clone(),
+            triggered: false,
+            input_tap: input_tap_in,
+            output_tap: output_tap_in,
+        },
+        DebuggerOutput {
+            channels: channels_out,
+            generation: 0,
+            shared_generation,
+            sample_rate,
+            requests: requests_out,
+            new_channels: new_channels_in,
+            freed: freed_out,
+            record_taps: record_taps_in,
+            stop_recording,
+            stereo_taps,
+            io_taps,
+            input_tap: ConsumerRingBuf::new(input_tap_out, STEREO_TAP_SIZE),
+            output_tap: ConsumerRingBuf::new(output_tap_out, STEREO_TAP_SIZE),
+            xy_channels: [Some(0), Some(1)],
+            console,
+            freeze,
+            layout: DebugLayout::default(),
+        },
+    )
+}
+
+/// Send the buffers of a channel to the editor to be freed. The ring has room for all of them
+/// unless the editor stopped updating, in which case they're freed on the calling thread.
+fn free_channel(freed: &mut Producer<FreedChannel>, channel: FreedChannel) {
+    if channel.producer.is_none() && channel.stats.is_none() && channel.record.is_none() {
+        return;
+    }
+    let _ = freed.push(channel);
+}
+
+impl DebuggerInput {
+    /// Pick up channels allocated by the editor. Called at the start of each block.
+    pub fn update(&mut self, sample_rate: f32) {
+        self.sample_rate.set(sample_rate);
+        self.console.update();
+        let triggered = self.freeze.is_triggered();
+        if triggered && !self.triggered {
+            // Triggered from the editor
+            self.start_post_trigger();
+        }
+        self.triggered = triggered;
+        let generation = self.shared_generation.load(Ordering::Relaxed);
+        if generation != self.generation {
+            self.generation = generation;
+            // Doesn't free anything here, the buffers go back to the editor
+            for channel in self.channels.drain(DEFAULT_CHANNELS..) {
+                let freed = FreedChannel {
+                    producer: channel.producer,
+                    stats: channel.stats_in,
+                    record: channel.record,
+                };
+                free_channel(&mut self.freed, freed);
+            }
+        }
+        while let Some(new_channel) = self.new_channels.pop() {
+            match self.channels.get_mut(new_channel.index) {
+                Some(channel) if new_channel.generation == self.generation => {
+                    channel.producer = Some(new_channel.producer);
+                    channel.stats_in = Some(new_channel.stats);
+                }
+                _ => {
+                    let freed = FreedChannel {
+                        producer: Some(new_channel.producer),
+                        stats: Some(new_channel.stats),
+                        record: None,
+                    };
+                    free_channel(&mut self.freed, freed);
+                }
+            }
+        }
+        if self.stop_recording.swap(false, Ordering::Relaxed) {
+            for channel in self.channels.iter_mut() {
+                let record = channel.record.take();
+                free_channel(
+                    &mut self.freed,
+                    FreedChannel {
+                        record,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        while let Some(tap) = self.record_taps.pop() {
+            match self.channels.get_mut(tap.index) {
+                Some(channel) if tap.generation == self.generation && channel.record.is_none() => {
+                    channel.record = Some(tap.record);
+                }
+                _ => {
+                    let record = Some(tap.record);
+                    free_channel(
+                        &mut self.freed,
+                        FreedChannel {
+                            record,
+                            ..Default::default()
+                        },
+                    );
+                }
+            }
+        }
+        for channel in self.channels.iter_mut() {
+            if let Some(stats_in) = &mut channel.stats_in {
+                if stats_in.reset.swap(false, Ordering::Relaxed) {
+                    channel.stats = ChannelStats::default();
+                }
+                channel.stats.end_block();
+                stats_in.input.write(channel.stats);
+            }
+        }
+    }
+
+    /// Index of the channel called `name`, registering it with a buffer of `size` values if
+    /// it doesn't exist. For vector channels `size` is the length of the arrays.
+    /// Returns None once `MAX_DEBUG_CHANNELS` are in use.
+    pub fn probe(&mut self, name: &str, kind: ProbeKind, size: usize) -> Option<usize> {
+        let min_size = match kind {
+            ProbeKind::Vector => 1,
+            _ => MIN_CHANNEL_SIZE,
+        };
+        let size = size.clamp(min_size, MAX_CHANNEL_SIZE);
+        let name = ChannelName::new(name);
+        if let Some(index) = self.channels.iter().position(|c| c.name == name) {
+            return Some(index);
+        }
+        if self.channels.len() >= MAX_DEBUG_CHANNELS || self.requests.is_full() {
+            return None;
+        }
+        let index = self.channels.len();
+        // Doesn't allocate, the capacity is reserved in `new_debugger`
+        self.channels.push(DebugChannelIn::new(name, kind, size));
+        let request = ChannelRequest {
+            generation: self.generation,
+            index,
+            name,
+            kind,
+            size,
+        };
+        if self.requests.push(request).is_err() {
+            return None;
+        }
+        Some(index)
+    }
+
+    /// Send `v` to channel `index`. Returns false if the value was dropped, it's still counted
+    /// in the channel stats. Vector channels only take whole arrays, see `push_vector`.
+    pub fn push(&mut self, index: usize, v: f32) -> bool {
+        let triggered = self.triggered;
+        match self.channels.get_mut(index) {
+            Some(channel) if channel.kind != ProbeKind::Vector => {
+                channel.stats.add(v);
+                // Recordings keep going while the channels are frozen
+                if let Some(record) = &mut channel.record {
+                    if record.producer.push(v).is_err() {
+                        record.dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                if !channel.take(1, triggered) {
+                    return false;
+                }
+                let sent = match &mut channel.producer {
+                    Some(producer) => producer.push(v).is_ok(),
+                    None => false,
+                };
+                if !sent {
+                    channel.stats.dropped += 1;
+                }
+                sent
+            }
+            _ => false,
+        }
+    }
+
+    /// Send integer `v` to channel `index`, clamped to `±MAX_EXACT_INT`. See `push`.
+    pub fn push_int(&mut self, index: usize, v: i64) -> bool {
+        self.push(index, v.clamp(-MAX_EXACT_INT, MAX_EXACT_INT) as f32)
+    }
+
+    /// Freeze all channels once they have their post trigger history. Does nothing if already
+    /// triggered.
+    pub fn trigger(&mut self, reason: TriggerReason) {
+        if self.freeze.trigger(reason) {
+            self.triggered = true;
+            self.start_post_trigger();
+        }
+    }
+
+    fn start_post_trigger(&mut self) {
+        let post_trigger = self.freeze.post_trigger.get().clamp(0.0, 1.0);
+        for channel in self.channels.iter_mut() {
+            channel.post_remaining = match channel.kind {
+                ProbeKind::Vector => channel.size,
+                _ => (channel.size as f32 * post_trigger) as usize,
+            };
+        }
+    }
+
+    /// Trigger if the plugin output has NaN or Inf and freezing on that is enabled
+    pub fn check_output(&mut self, output: [&[f32]; 2]) {
+        if self.triggered || !self.freeze.freeze_on_non_finite.load(Ordering::Relaxed) {
+            return;
+        }
+        if output.iter().any(|buf| buf.iter().any(|v| !v.is_finite())) {
+            self.trigger(TriggerReason::NonFiniteOutput);
+        }
+    }
+
+    /// Whether the plugin output should be silenced
+    pub fn muted(&self) -> bool {
+        self.triggered && self.freeze.mute.load(Ordering::Relaxed)
+    }
+
+    /// Print a message to the console, see `ConsoleInput::print`
+    pub fn print(&mut self, text: &str, value: LogValue) -> bool {
+        self.console.print(text, value)
+    }
+
+    /// Send the plugin input, before the script runs, and output, after the master gain, of this
+    /// block to the goniometer if it's shown, and to the `IO_TAP_CHANNELS` if the I/O taps are
+    /// enabled. Frames that don't fit are dropped.
+    pub fn tap_plugin_io(&mut self, input: [&[f32]; 2], output: [&[f32]; 2]) {
+        if self.io_taps.load(Ordering::Relaxed) {
+            let buffers = [input[0], input[1], output[0], output[1]];
+            for (name, buffer) in IO_TAP_CHANNELS.iter().zip(buffers.iter()) {
+                if let Some(index) = self.probe(name, ProbeKind::Samples, DEFAULT_CHANNEL_SIZE) {
+                    for v in buffer.iter() {
+                        self.push(index, *v);
+                    }
+                }
+            }
+        }
+        if self.triggered || !self.stereo_taps.load(Ordering::Relaxed) {
+            return;
+        }
+        for (tap, [left, right]) in
+            [(&mut self.input_tap, input), (&mut self.output_tap, output)].iter_mut()
+        {
+            for frame in left.iter().zip(right.iter()) {
+                if tap.push((*frame.0, *frame.1)).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Send a whole array to vector channel `index`. Arrays are cut or zero padded to the length
+    /// the channel was probed with. Returns false if the array was dropped.
+    pub fn push_vector(&mut self, index: usize, values: &[f32]) -> bool {
+        let triggered = self.triggered;
+        match self.channels.get_mut(index) {
+            Some(channel) if channel.kind == ProbeKind::Vector => {
+                let size = channel.size;
+                let len = values.len().min(size);
+                for v in values[..len].iter() {
+                    channel.stats.add(*v);
+                }
+                for _ in len..size {
+                    channel.stats.add(0.0);
+                }
+                if !channel.take(size, triggered) {
+                    return false;
+                }
+                let producer = match &mut channel.producer {
+                    Some(producer) if producer.remaining() >= size => producer,
+                    _ => {
+                        channel.stats.dropped += size as u64;
+                        return false;
+                    }
+                };
+                producer.push_slice(&values[..len]);
+                for _ in len..size {
+                    let _ = producer.push(0.0);
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+impl DebuggerOutput {
+    /// Allocate buffers for new channels and pull in the latest values.
+    /// Called once per frame by the debug panel.
+    pub fn update(&mut self) {
+        while let Some(request) = self.requests.pop() {
+            if request.generation != self.generation {
+                continue;
+            }
+            let (prod, cons) =
+                RingBuffer::<f32>::new(ring_size(request.kind, request.size)).split();
+            let (stats_in, stats_out, reset_stats) = new_stats();
+            let new_channel = NewChannel {
+                generation: request.generation,
+                index: request.index,
+                producer: prod,
+                stats: stats_in,
+            };
+            if self.new_channels.push(new_channel).is_err() {
+                continue;
+            }
+            if self.channels.len() <= request.index {
+                self.channels.resize_with(request.index + 1, || None);
+            }
+            let mut channel = DebugChannel::new(
+                request.name.as_str().to_string(),
+                request.kind,
+                cons,
+                request.size,
+                stats_out,
+                reset_stats,
+            );
+            if let Some(saved) = self.layout.channel(&channel.name) {
+                saved.apply(&mut channel);
+            }
+            for (selected, name) in self
+                .xy_channels
+                .iter_mut()
+                .zip(self.layout.xy_channels.iter())
+            {
+                if selected.is_none() && *name == channel.name {
+                    *selected = Some(request.index);
+                }
+            }
+            self.channels[request.index] = Some(channel);
+        }
+        // Buffers of cleared or stale channels, dropped here rather than on the audio thread
+        while self.freed.pop().is_some() {}
+        for channel in self.channels.iter_mut().flatten() {
+            channel.consume();
+        }
+        self.input_tap.consume();
+        self.output_tap.consume();
+    }
+
+    /// The audio thread only sends its input and output while the goniometer is shown
+    pub fn set_stereo_taps(&self, enabled: bool) {
+        self.stereo_taps.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn io_taps(&self) -> bool {
+        self.io_taps.load(Ordering::Relaxed)
+    }
+
+    /// Have the audio thread send the plugin input and output to debugger channels
+    pub fn set_io_taps(&self, enabled: bool) {
+        self.io_taps.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Sample rate of the process function
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate.get()
+    }
+
+    /// Names of the XY view channels, the saved ones for channels that aren't probed yet
+    fn xy_channel_names(&self) -> [String; 2] {
+        let name = |axis: usize| match self.xy_channels[axis].and_then(|i| self.channels.get(i)) {
+            Some(Some(channel)) => channel.name.clone(),
+            _ => self.layout.xy_channels[axis].clone(),
+        };
+        [name(0), name(1)]
+    }
+
+    /// Current settings, including saved ones of channels that aren't probed by this script
+    pub fn layout(&self) -> DebugLayout {
+        let mut layout = DebugLayout {
+            io_taps: self.io_taps(),
+            xy_channels: self.xy_channel_names(),
+            channels: self
+                .channels
+                .iter()
+                .flatten()
+                .map(ChannelLayout::from_channel)
+                .collect(),
+        };
+        for saved in self.layout.channels.iter() {
+            if layout.channel(&saved.name).is_none() {
+                layout.channels.push(saved.clone());
+            }
+        }
+        layout
+    }
+
+    /// Restore saved settings. Channels probed later pick theirs up when they're created.
+    pub fn set_layout(&mut self, layout: DebugLayout) {
+        self.set_io_taps(layout.io_taps);
+        for (selected, name) in self.xy_channels.iter_mut().zip(layout.xy_channels.iter()) {
+            *selected = self
+                .channels
+                .iter()
+                .position(|channel| matches!(channel, Some(channel) if channel.name == *name));
+        }
+        for channel in self.channels.iter_mut().flatten() {
+            if let Some(saved) = layout.channel(&channel.name) {
+                saved.apply(channel);
+            }
+        }
+        self.layout = layout;
+    }
+
+    /// Have the audio thread copy every value sent to channel `index` into a buffer of `size`
+    /// values, until `stop_recording`. Values that don't fit are counted in `dropped`.
+    /// Returns None for vector channels, or if the channel doesn't exist.
+    pub fn record_channel(
+        &mut self,
+        index: usize,
+        size: usize,
+        dropped: Arc<AtomicUsize>,
+    ) -> Option<Consumer<f32>> {
+        match self.channels.get(index) {
+            Some(Some(channel)) if channel.kind != ProbeKind::Vector => {}
+            _ => return None,
+        }
+        let (producer, consumer) = RingBuffer::<f32>::new(size).split();
+        let tap = RecordTap {
+            generation: self.generation,
+            index,
+            record: RecordIn { producer, dropped },
+        };
+        self.record_taps.push(tap).ok()?;
+        Some(consumer)
+    }
+
+    /// Flag shared with the audio thread, setting it stops the copies made for `record_channel`
+    pub fn stop_recording(&self) -> Arc<AtomicBool> {
+        self.stop_recording.clone()
+    }
+
+    /// Remove all probed channels, keeping the default ones.
+    pub fn clear(&mut self) {
+        // Probed XY channels are found again by name when they're probed after the clear
+        self.layout.xy_channels = self.xy_channel_names();
+        for selected in self.xy_channels.iter_mut() {
+            if matches!(selected, Some(i) if *i >= DEFAULT_CHANNELS) {
+                *selected = None;
+            }
+        }
+        self.generation += 1;
+        self.shared_generation
+            .store(self.generation, Ordering::Relaxed);
+        self.channels.truncate(DEFAULT_CHANNELS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_probe_channels() {
+        let (mut debug_in, mut debug_out) = new_debugger();
+        assert!(debug_in.push(0, 1.0));
+        let env = debug_in.probe("env", ProbeKind::Samples, 128).unwrap();
+        assert_eq!(env, DEFAULT_CHANNELS);
+        assert_eq!(debug_in.probe("env", ProbeKind::Samples, 128), Some(env));
+        // Dropped until the editor allocated the channel
+        assert!(!debug_in.push(env, 1.0));
+        debug_out.update();
+        debug_in.update(44100.0);
+        assert!(debug_in.push(env, 1.0));
+        debug_out.update();
+        let channel = debug_out.channels[env].as_mut().unwrap();
+        assert_eq!(channel.name, "env");
+        assert_eq!(channel.data.iter().last(), Some(&1.0));
+        // The value pushed before allocation is counted as dropped
+        debug_in.update(44100.0);
+        let stats = channel.stats();
+        assert_eq!((stats.count, stats.dropped), (2, 1));
+
+        debug_out.clear();
+        debug_in.update(44100.0);
+        assert!(!debug_in.push(env, 1.0));
+        // The buffers of the cleared channel are freed by the editor
+        assert_eq!(debug_out.freed.len(), 1);
+        debug_out.update();
+        assert!(debug_out.freed.is_empty());
+        assert!(debug_in.push(0, 1.0));
+        assert_eq!(
+            debug_in.probe("gr_db", ProbeKind::Block, 128),
+            Some(DEFAULT_CHANNELS)
+        );
+    }
+
+    #[test]
+    fn test_xy_channels_by_name() {
+        let (mut debug_in, mut debug_out) = new_debugger();
+        let a = debug_in.probe("a", ProbeKind::Samples, 128).unwrap();
+        let b = debug_in.probe("b", ProbeKind::Samples, 128).unwrap();
+        debug_out.update();
+        debug_out.xy_channels = [Some(b), Some(0)];
+        let layout = debug_out.layout();
+        assert_eq!(layout.xy_channels, ["b".to_string(), "0".to_string()]);
+
+        // Probed in a different order after a recompile
+        debug_out.clear();
+        debug_in.update(44100.0);
+        assert_eq!(debug_out.xy_channels, [None, Some(0)]);
+        assert_eq!(debug_out.layout().xy_channels, layout.xy_channels);
+        assert_eq!(debug_in.probe("b", ProbeKind::Samples, 128), Some(a));
+        debug_out.update();
+        assert_eq!(debug_out.xy_channels, [Some(a), Some(0)]);
+
+        // Reloaded with a saved channel that isn't probed
+        let mut saved = layout;
+        saved.xy_channels[1] = "c".to_string();
+        debug_out.set_layout(saved);
+        assert_eq!(debug_out.xy_channels, [Some(a), None]);
+        let c = debug_in.probe("c", ProbeKind::Samples, 128).unwrap();
+        debug_out.update();
+        assert_eq!(debug_out.xy_channels, [Some(a), Some(c)]);
+    }
+
+    #[test]
+    fn test_record_channel() {
+        let (mut debug_in, mut debug_out) = new_debugger();
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut recorded = debug_out.record_channel(0, 2, dropped.clone()).unwrap();
+        debug_in.update(44100.0);
+        for v in [1.0, 2.0, 3.0].iter() {
+            debug_in.push(0, *v);
+        }
+        assert_eq!((recorded.pop(), recorded.pop()), (Some(1.0), Some(2.0)));
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+        debug_out.stop_recording().store(true, Ordering::Relaxed);
+        debug_in.update(44100.0);
+        debug_in.push(0, 4.0);
+        assert!(recorded.pop().is_none());
+        assert_eq!(debug_out.freed.len(), 1);
+    }
+
+    #[test]
+    fn test_vector_channel() {
+        let (mut debug_in, mut debug_out) = new_debugger();
+        let state = debug_in.probe("state", ProbeKind::Vector, 16).unwrap();
+        debug_out.update();
+        debug_in.update(44100.0);
+        assert!(!debug_in.push(state, 1.0));
+        assert!(debug_in.push_vector(state, &[1.0, 2.0, 3.0]));
+        assert!(debug_in.push_vector(state, &[4.0; 20]));
+        debug_out.update();
+        let channel = debug_out.channels[state].as_ref().unwrap();
+        assert!(channel.data.iter().all(|v| *v == 4.0));
+    }
+
+    #[test]
+    fn test_io_taps() {
+        let (mut debug_in, mut debug_out) = new_debugger();
+        let (input, output) = ([0.5; 8], [0.25; 8]);
+        debug_in.tap_plugin_io([&input, &input], [&output, &output]);
+        debug_out.update();
+        assert_eq!(debug_out.channels.len(), DEFAULT_CHANNELS);
+
+        debug_out.set_io_taps(true);
+        debug_in.tap_plugin_io([&input, &input], [&output, &output]);
+        debug_out.update();
+        debug_in.update(44100.0);
+        debug_in.tap_plugin_io([&input, &input], [&output, &output]);
+        debug_out.update();
+        let output_r = debug_out.channels[DEFAULT_CHANNELS + 3].as_ref().unwrap();
+        assert_eq!(output_r.name, "output R");
+        assert_eq!(output_r.new_values, 8);
+        assert_eq!(output_r.data.iter().last(), Some(&0.25));
+    }
+
+    #[test]
+    fn test_trigger() {
+        let (mut debug_in, debug_out) = new_debugger();
+        debug_out
+            .freeze
+            .freeze_on_non_finite
+            .store(true, Ordering::Relaxed);
+        debug_in.check_output([&[0.0; 4], &[0.0; 4]]);
+        assert!(debug_out.freeze.reason().is_none());
+        debug_in.check_output([&[0.0, f32::NAN], &[0.0; 2]]);
+        assert_eq!(
+            debug_out.freeze.reason(),
+            Some(TriggerReason::NonFiniteOutput)
+        );
+        // Half of the channel is filled after the trigger, then it's frozen
+        for _ in 0..DEFAULT_CHANNEL_SIZE / 2 {
+            assert!(debug_in.push(0, 1.0));
+        }
+        assert!(!debug_in.push(0, 1.0));
+        debug_out.freeze.resume();
+        debug_in.update(44100.0);
+        assert!(debug_in.push(0, 1.0));
+    }
+}
@@ -0,0 +1,79 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use crate::atomic_f32::AtomicF32;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TriggerReason {
+    /// The script called `dbg.trigger` with a true condition
+    Script,
+    /// The plugin output was NaN or infinite
+    NonFiniteOutput,
+    /// The trigger button in the debug panel
+    Manual,
+}
+
+impl TriggerReason {
+    fn from_usize(v: usize) -> Option<Self> {
+        match v {
+            1 => Some(TriggerReason::Script),
+            2 => Some(TriggerReason::NonFiniteOutput),
+            3 => Some(TriggerReason::Manual),
+            _ => None,
+        }
+    }
+
+    fn to_usize(self) -> usize {
+        match self {
+            TriggerReason::Script => 1,
+            TriggerReason::NonFiniteOutput => 2,
+            TriggerReason::Manual => 3,
+        }
+    }
+}
+
+/// Trigger settings and state shared by both sides of the debugger.
+///
+/// Once triggered, each channel takes `post_trigger` of its length in new values and then stops,
+/// so the editor is left with the history around the trigger until it's resumed.
+pub struct FreezeControl {
+    // 0 while running, otherwise the TriggerReason
+    reason: AtomicUsize,
+    pub freeze_on_non_finite: AtomicBool,
+    /// Silence the plugin output while triggered
+    pub mute: AtomicBool,
+    /// Part of each channel filled after the trigger, from 0.0 to 1.0
+    pub post_trigger: AtomicF32,
+}
+
+impl Default for FreezeControl {
+    fn default() -> Self {
+        FreezeControl {
+            reason: AtomicUsize::new(0),
+            freeze_on_non_finite: AtomicBool::new(false),
+            mute: AtomicBool::new(false),
+            post_trigger: AtomicF32::new(0.5),
+        }
+    }
+}
+
+impl FreezeControl {
+    /// Returns true if this call triggered, false if it was already triggered
+    pub fn trigger(&self, reason: TriggerReason) -> bool {
+        self.reason
+            .compare_exchange(0, reason.to_usize(), Ordering::Relaxed, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    pub fn resume(&self) {
+        self.reason.store(0, Ordering::Relaxed);
+    }
+
+    /// None while running
+    pub fn reason(&self) -> Option<TriggerReason> {
+        TriggerReason::from_usize(self.reason.load(Ordering::Relaxed))
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.reason.load(Ordering::Relaxed) != 0
+    }
+}
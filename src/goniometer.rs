@@ -0,0 +1,134 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+
+use egui::{
+    pos2, remap_clamp, vec2, Align2, Color32, Rect, Response, Sense, Stroke, TextStyle, Ui,
+};
+
+const CORRELATION_HEIGHT: f32 = 16.0;
+// Below this energy a pair of signals is treated as silence and has no correlation
+const SILENCE: f32 = 1e-9;
+
+/// Phase correlation of `pairs`, from -1.0 (out of phase) to 1.0 (identical).
+/// 0.0 for silence.
+pub fn correlation(pairs: impl Iterator<Item = (f32, f32)>) -> f32 {
+    let (mut lr, mut ll, mut rr) = (0.0, 0.0, 0.0);
+    for (l, r) in pairs.filter(|(l, r)| l.is_finite() && r.is_finite()) {
+        lr += l * r;
+        ll += l * l;
+        rr += r * r;
+    }
+    if ll * rr > SILENCE {
+        (lr / (ll * rr).sqrt()).clamp(-1.0, 1.0)
+    } else {
+        0.0
+    }
+}
+
+/// Plot of `x` against `y` scaled to the largest magnitude. With `mid_side` the plot is rotated
+/// 45 degrees like a goniometer, mono is a vertical line and out of phase a horizontal one.
+pub fn xy_ui(ui: &mut Ui, pairs: &[(f32, f32)], mid_side: bool) -> Response {
+    let size = ui.available_width().min(300.0);
+    let (response, painter) = ui.allocate_painter(vec2(size, size), Sense::hover());
+    let rect = response.rect;
+    let visuals = ui.visuals().clone();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    let grid_stroke = Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color);
+    let c = rect.center();
+    painter.line_segment(
+        [pos2(rect.left(), c.y), pos2(rect.right(), c.y)],
+        grid_stroke,
+    );
+    painter.line_segment(
+        [pos2(c.x, rect.top()), pos2(c.x, rect.bottom())],
+        grid_stroke,
+    );
+    if mid_side {
+        painter.line_segment([rect.left_top(), rect.right_bottom()], grid_stroke);
+        painter.line_segment([rect.right_top(), rect.left_bottom()], grid_stroke);
+        let text_color = visuals.weak_text_color();
+        painter.text(
+            rect.left_top() + vec2(2.0, 2.0),
+            Align2::LEFT_TOP,
+            "L",
+            TextStyle::Small,
+            text_color,
+        );
+        painter.text(
+            rect.right_top() + vec2(-2.0, 2.0),
+            Align2::RIGHT_TOP,
+            "R",
+            TextStyle::Small,
+            text_color,
+        );
+    }
+
+    let points = pairs
+        .iter()
+        .filter(|(x, y)| x.is_finite() && y.is_finite())
+        .map(|(x, y)| {
+            if mid_side {
+                ((y - x) * FRAC_1_SQRT_2, (x + y) * FRAC_1_SQRT_2)
+            } else {
+                (*x, *y)
+            }
+        });
+    let max = points
+        .clone()
+        .fold(0.0f32, |max, (x, y)| max.max(x.abs()).max(y.abs()));
+    if max > 0.0 {
+        let scale = 0.5 * rect.width() / max;
+        let color = visuals.selection.bg_fill;
+        for (x, y) in points {
+            let p = c + vec2(x * scale, -y * scale);
+            painter.rect_filled(Rect::from_center_size(p, vec2(1.5, 1.5)), 0.0, color);
+        }
+    }
+    response
+}
+
+/// Horizontal bar from -1.0 to 1.0 showing a correlation value
+pub fn correlation_meter_ui(ui: &mut Ui, correlation: f32) -> Response {
+    let width = ui.available_width().min(300.0);
+    let (response, painter) = ui.allocate_painter(vec2(width, CORRELATION_HEIGHT), Sense::hover());
+    let rect = response.rect;
+    let visuals = ui.visuals().clone();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    let zero = rect.center().x;
+    let x = remap_clamp(correlation, -1.0..=1.0, rect.x_range());
+    let color = if correlation < 0.0 {
+        Color32::from_rgb(230, 60, 50)
+    } else {
+        Color32::from_rgb(70, 200, 90)
+    };
+    painter.rect_filled(
+        Rect::from_x_y_ranges(zero.min(x)..=zero.max(x), rect.y_range()),
+        0.0,
+        color,
+    );
+    painter.line_segment(
+        [pos2(zero, rect.top()), pos2(zero, rect.bottom())],
+        Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color),
+    );
+    painter.text(
+        rect.right_center() + vec2(-2.0, 0.0),
+        Align2::RIGHT_CENTER,
+        format!("{:+.2}", correlation),
+        TextStyle::Small,
+        visuals.text_color(),
+    );
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_correlation() {
+        let sine: Vec<f32> = (0..256).map(|i| (i as f32 * 0.1).sin()).collect();
+        let mono = correlation(sine.iter().map(|v| (*v, *v)));
+        assert!((mono - 1.0).abs() < 1e-5);
+        let inverted = correlation(sine.iter().map(|v| (*v, -*v)));
+        assert!((inverted + 1.0).abs() < 1e-5);
+        assert_eq!(correlation(sine.iter().map(|v| (*v, 0.0))), 0.0);
+    }
+}
@@ -1,95 +1,577 @@
+use std::{path::Path, sync::atomic::Ordering};
+
 use egui::{
-    plot::{Line, Plot, Value, Values},
-    Ui,
+    plot::{Legend, Line, Plot, Value, Values},
+    pos2, vec2, Color32, Grid, Rect, Sense, Stroke, Ui,
 };
 
-use crate::compiler_interface::CompilerEditorState;
+use crate::{
+    compiler_interface::{CompilerEditorState, MIN_WAVEFORM_SIZE},
+    correlation_match::display::WeightWindow,
+    debugger::{ChannelView, DebugChannel, DebuggerOutput, ProbeKind, MAX_EXACT_INT},
+    freeze::TriggerReason,
+    goniometer::{correlation, correlation_meter_ui, xy_ui},
+    pitch::{note_name, PitchDetector},
+    recorder::recorder_ui,
+    scope::scope_ui,
+    spectrum::spectrum_ui,
+    state_inspector::state_ui,
+};
 
-fn decay_time_to_factor(time: f32) -> f32 {
-    // arbitrary constant that gives a useful range
-    1. - (-1. / 6. / time).exp()
-}
+const TABLE_ROWS: usize = 16;
+const STRIP_HEIGHT: f32 = 16.0;
+const BAR_CHART_HEIGHT: f32 = 150.0;
 
 pub fn graphs_ui(ui: &mut Ui, state: &mut CompilerEditorState) {
+    let mut debug_out = match state.debug_out.try_lock() {
+        Ok(debug_out) => debug_out,
+        Err(_) => return,
+    };
+    debug_out.update();
+    ui.horizontal(|ui| {
+        if ui.button("Clear Probes").clicked() {
+            debug_out.clear();
+        }
+        let mut io_taps = debug_out.io_taps();
+        if ui.checkbox(&mut io_taps, "Tap Plugin I/O").changed() {
+            debug_out.set_io_taps(io_taps);
+        }
+    });
+    if let Ok(mut recorder) = state.recorder.try_lock() {
+        if recorder_ui(ui, &mut recorder, &debug_out) {
+            if let Ok(projects) = state.shared_ctx.projects.try_lock() {
+                let name = Path::new(&state.file_name)
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .unwrap_or("recording");
+                recorder.start(&mut debug_out, &projects.project_paths.projects_dir, name);
+            }
+        }
+    }
+    trigger_section_ui(ui, &debug_out);
+    process_state_section_ui(ui, state);
+    xy_section_ui(ui, &mut debug_out);
+    goniometer_section_ui(ui, &mut debug_out);
+    let sample_rate = debug_out.sample_rate();
+    let samples = sample_channels(&debug_out.channels);
     egui::ScrollArea::vertical()
         .enable_scrolling(true)
         .id_source("log")
         .show(ui, |ui| {
-            let mut debug_out = state.debug_out.lock().unwrap();
-            for i in 0..debug_out.consumers.len() {
-                debug_out.consumers[i].consume();
-                let waveform = &mut state.waveforms[i];
-
-                ui.label(format!(
-                    "dbg.show({}, {:.6})",
-                    i, debug_out.consumers[i].data[0]
-                ));
-
-                ui.checkbox(&mut waveform.enable_waveform, "Waveform");
-
-                ui.checkbox(&mut waveform.enable_smoothing, "Smoothing");
-
-                if waveform.enable_waveform {
-                    if waveform.enable_smoothing {
-                        ui.add(
-                            egui::Slider::new(&mut waveform.memory_decay, 0.1..=2.0)
-                                .text("Memory Decay"),
-                        );
-
-                        ui.add(
-                            egui::Slider::new(&mut waveform.display_decay, 0.1..=2.0)
-                                .text("Display Decay"),
-                        );
-
-                        for (n, buf) in debug_out.consumers[i]
-                            .iter()
-                            .zip(waveform.buffer.get_buffer_mut())
-                        {
-                            *buf = *n
+            for (i, channel) in debug_out.channels.iter_mut().enumerate() {
+                if let Some(channel) = channel {
+                    channel_ui(ui, i, channel, sample_rate, &samples);
+                }
+            }
+        });
+}
+
+/// Names of the sample channels, with the latest values of the ones lined up with another
+/// channel's waveform
+fn sample_channels(channels: &[Option<DebugChannel>]) -> Vec<(String, Vec<f32>)> {
+    let aligned: Vec<&String> = channels
+        .iter()
+        .flatten()
+        .flat_map(|channel| channel.waveform.aligned.iter())
+        .collect();
+    channels
+        .iter()
+        .flatten()
+        .filter(|channel| channel.kind == ProbeKind::Samples)
+        .map(|channel| {
+            let data = if aligned.contains(&&channel.name) {
+                channel.data.iter().copied().collect()
+            } else {
+                Vec::new()
+            };
+            (channel.name.clone(), data)
+        })
+        .collect()
+}
+
+/// Field values of the running `ProcessState`, copied by the audio thread while shown
+fn process_state_section_ui(ui: &mut Ui, state: &CompilerEditorState) {
+    egui::CollapsingHeader::new("Process State").show(ui, |ui| {
+        let layout = state.shared_ctx.process_layout.lock().unwrap();
+        let layout = match &*layout {
+            Some(layout) => layout,
+            None => {
+                ui.label("The ProcessState layout is not known");
+                return;
+            }
+        };
+        let mut snapshot = state.shared_ctx.state_snapshot.lock().unwrap();
+        state_ui(ui, layout, &snapshot.data);
+        if layout.matches_compiled() {
+            snapshot.request(layout.size);
+        }
+    });
+}
+
+/// Freezing the channels on a trigger from the script, a NaN in the output or the button
+fn trigger_section_ui(ui: &mut Ui, debug_out: &DebuggerOutput) {
+    let freeze = &debug_out.freeze;
+    ui.horizontal(|ui| match freeze.reason() {
+        Some(reason) => {
+            let reason = match reason {
+                TriggerReason::Script => "script",
+                TriggerReason::NonFiniteOutput => "NaN/Inf in output",
+                TriggerReason::Manual => "button",
+            };
+            ui.colored_label(
+                Color32::from_rgb(230, 140, 40),
+                format!("Frozen by {}", reason),
+            );
+            if ui.button("Resume").clicked() {
+                freeze.resume();
+            }
+        }
+        None => {
+            ui.label("Running");
+            if ui.button("Trigger").clicked() {
+                freeze.trigger(TriggerReason::Manual);
+            }
+        }
+    });
+    egui::CollapsingHeader::new("Trigger").show(ui, |ui| {
+        let mut on_non_finite = freeze.freeze_on_non_finite.load(Ordering::Relaxed);
+        if ui
+            .checkbox(&mut on_non_finite, "Freeze on NaN/Inf Output")
+            .changed()
+        {
+            freeze
+                .freeze_on_non_finite
+                .store(on_non_finite, Ordering::Relaxed);
+        }
+        let mut mute = freeze.mute.load(Ordering::Relaxed);
+        if ui.checkbox(&mut mute, "Mute While Frozen").changed() {
+            freeze.mute.store(mute, Ordering::Relaxed);
+        }
+        let mut post_trigger = freeze.post_trigger.get();
+        if ui
+            .add(egui::Slider::new(&mut post_trigger, 0.0..=1.0).text("Post Trigger"))
+            .changed()
+        {
+            freeze.post_trigger.set(post_trigger);
+        }
+    });
+}
+
+/// One channel plotted against another, with the ends of both channels lined up
+fn xy_section_ui(ui: &mut Ui, debug_out: &mut DebuggerOutput) {
+    egui::CollapsingHeader::new("XY").show(ui, |ui| {
+        let names: Vec<(usize, String)> = debug_out
+            .channels
+            .iter()
+            .enumerate()
+            .filter_map(|(i, channel)| match channel {
+                Some(channel) if channel.kind != ProbeKind::Vector => {
+                    Some((i, channel.name.clone()))
+                }
+                _ => None,
+            })
+            .collect();
+        ui.horizontal(|ui| {
+            for (axis, label) in ["X", "Y"].iter().enumerate() {
+                let selected = &mut debug_out.xy_channels[axis];
+                let selected_name = names
+                    .iter()
+                    .find(|(i, _)| Some(*i) == *selected)
+                    .map(|(_, name)| name.as_str())
+                    .unwrap_or("-");
+                egui::ComboBox::from_label(*label)
+                    .selected_text(selected_name)
+                    .show_ui(ui, |ui| {
+                        for (i, name) in names.iter() {
+                            ui.selectable_value(selected, Some(*i), name);
                         }
+                    });
+            }
+        });
+        let [x, y] = debug_out.xy_channels;
+        let channel = |index: Option<usize>| index.and_then(|i| debug_out.channels.get(i));
+        if let (Some(Some(x)), Some(Some(y))) = (channel(x), channel(y)) {
+            let (x_len, y_len) = (x.data.data.len(), y.data.data.len());
+            let n = x_len.min(y_len);
+            let pairs: Vec<(f32, f32)> = x
+                .data
+                .iter()
+                .skip(x_len - n)
+                .zip(y.data.iter().skip(y_len - n))
+                .map(|(x, y)| (*x, *y))
+                .collect();
+            correlation_meter_ui(ui, correlation(pairs.iter().copied()));
+            xy_ui(ui, &pairs, false);
+        }
+    });
+}
 
-                        waveform.buffer.update_match(
-                            true,
-                            decay_time_to_factor(waveform.memory_decay),
-                            decay_time_to_factor(waveform.display_decay),
-                        );
-
-                        waveform
-                            .buffer
-                            .update_display(decay_time_to_factor(waveform.display_decay));
-
-                        let data = waveform.buffer.get_display();
-                        let line = Line::new(Values::from_values_iter(
-                            data.iter()
-                                .enumerate()
-                                .map(|(i, v)| Value::new(i as f32, *v)),
-                        ));
-                        ui.add(
-                            Plot::new(format!("debug{}", i))
-                                .line(line)
-                                .view_aspect(1.0)
-                                .allow_drag(false)
-                                .allow_zoom(false)
-                                .show_x(false)
-                                .show_axes([false, true]),
-                        );
+/// Stereo image and phase correlation of the plugin input and output
+fn goniometer_section_ui(ui: &mut Ui, debug_out: &mut DebuggerOutput) {
+    let shown = egui::CollapsingHeader::new("Goniometer")
+        .show(ui, |ui| {
+            for (label, tap) in [
+                ("Input", &debug_out.input_tap),
+                ("Output", &debug_out.output_tap),
+            ]
+            .iter()
+            {
+                ui.label(*label);
+                let pairs: Vec<(f32, f32)> = tap.iter().copied().collect();
+                correlation_meter_ui(ui, correlation(pairs.iter().copied()));
+                xy_ui(ui, &pairs, true);
+            }
+        })
+        .body_returned
+        .is_some();
+    debug_out.set_stereo_taps(shown);
+}
+
+fn channel_ui(
+    ui: &mut Ui,
+    i: usize,
+    channel: &mut DebugChannel,
+    sample_rate: f32,
+    samples: &[(String, Vec<f32>)],
+) {
+    let last = *channel.data.iter().last().unwrap_or(&0.0);
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut channel.visible, "");
+        match channel.kind {
+            ProbeKind::Samples | ProbeKind::Block => {
+                ui.label(format!("{} ({}) {:.6}", channel.name, i, last))
+            }
+            ProbeKind::Integer => ui.label(format!("{} ({}) {}", channel.name, i, int_label(last))),
+            ProbeKind::Bool => ui.label(format!("{} ({}) {}", channel.name, i, last > 0.5)),
+            ProbeKind::Vector => ui.label(format!(
+                "{} ({}) [{}]",
+                channel.name,
+                i,
+                channel.data.data.len()
+            )),
+        };
+    });
+    if !channel.visible {
+        ui.separator();
+        return;
+    }
+    stats_ui(ui, i, channel);
+    match channel.kind {
+        ProbeKind::Samples => {
+            ui.horizontal(|ui| {
+                ui.selectable_value(&mut channel.view, ChannelView::Waveform, "Waveform");
+                ui.selectable_value(&mut channel.view, ChannelView::Scope, "Scope");
+                ui.selectable_value(&mut channel.view, ChannelView::Spectrum, "Spectrum");
+            });
+            pitch_ui(ui, channel, sample_rate);
+            match channel.view {
+                ChannelView::Waveform => waveform_ui(ui, i, channel, samples),
+                ChannelView::Scope => {
+                    let data = channel.data.iter().copied().collect::<Vec<f32>>();
+                    channel.scope.update(&data, channel.new_values);
+                    scope_ui(ui, format!("scope{}", i), &mut channel.scope, data.len());
+                }
+                ChannelView::Spectrum => spectrum_channel_ui(ui, channel, sample_rate),
+            }
+        }
+        ProbeKind::Block | ProbeKind::Integer => table_ui(ui, i, channel),
+        ProbeKind::Bool => strip_ui(ui, channel),
+        ProbeKind::Vector => bar_chart_ui(ui, channel),
+    }
+    ui.separator();
+}
+
+/// Stats of all values sent since the last reset, including ones the plots never received
+fn stats_ui(ui: &mut Ui, i: usize, channel: &mut DebugChannel) {
+    let stats = channel.stats();
+    egui::CollapsingHeader::new("Stats")
+        .id_source(format!("debug_stats{}", i))
+        .show(ui, |ui| {
+            Grid::new(format!("debug_stats_grid{}", i)).show(ui, |ui| {
+                if stats.count > stats.nan + stats.inf {
+                    ui.label(format!("Min {:.6}", stats.min));
+                    ui.label(format!("Max {:.6}", stats.max));
+                    ui.label(format!("Peak {:.1} dB", stats.peak_db()));
+                    ui.end_row();
+                }
+                ui.label(format!("Mean {:.6}", stats.mean()));
+                ui.label(format!("RMS {:.6}", stats.rms()));
+                ui.label(format!("DC {:.6}", stats.dc_offset));
+                ui.end_row();
+                let warning = Color32::from_rgb(230, 140, 40);
+                for (name, count) in [
+                    ("NaN", stats.nan),
+                    ("Inf", stats.inf),
+                    ("Denormal", stats.denormal),
+                ]
+                .iter()
+                {
+                    if *count > 0 {
+                        ui.colored_label(warning, format!("{} {}", name, count));
+                    } else {
+                        ui.label(format!("{} 0", name));
+                    }
+                }
+                ui.end_row();
+                ui.label(format!("Count {}", stats.count));
+                ui.label(format!("Dropped {}", stats.dropped));
+                if ui.button("Reset").clicked() {
+                    channel.reset_stats();
+                }
+                ui.end_row();
+            });
+        });
+}
+
+/// Fundamental frequency of the channel with the nearest note
+fn pitch_ui(ui: &mut Ui, channel: &mut DebugChannel, sample_rate: f32) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut channel.show_pitch, "Pitch");
+        if !channel.show_pitch {
+            return;
+        }
+        let data = channel.data.iter().copied().collect::<Vec<f32>>();
+        let pitch = channel
+            .pitch
+            .get_or_insert_with(|| PitchDetector::new(data.len()));
+        match pitch.frequency(&data, sample_rate) {
+            Some(freq) => {
+                let (note, cents) = note_name(freq);
+                ui.label(format!("{:.1} Hz {} {:+.0} cents", freq, note, cents))
+            }
+            None => ui.label("-"),
+        };
+    });
+}
+
+/// Latest values, newest first
+fn table_ui(ui: &mut Ui, i: usize, channel: &DebugChannel) {
+    let integer = channel.kind == ProbeKind::Integer;
+    Grid::new(format!("debug_table{}", i))
+        .striped(true)
+        .show(ui, |ui| {
+            let len = channel.data.data.len();
+            for (age, v) in channel
+                .data
+                .iter()
+                .rev()
+                .take(TABLE_ROWS.min(len))
+                .enumerate()
+            {
+                ui.label(format!("-{}", age));
+                if integer && v.abs() >= MAX_EXACT_INT as f32 {
+                    ui.colored_label(Color32::from_rgb(230, 140, 40), int_label(*v))
+                        .on_hover_text("Integer probes are clamped to where f32 is exact");
+                } else if integer {
+                    ui.label(int_label(*v));
+                } else {
+                    ui.label(format!("{:.6}", v));
+                }
+                ui.end_row();
+            }
+        });
+}
+
+/// Integer probe value, values at the limit were clamped and show as a bound
+fn int_label(v: f32) -> String {
+    let v = v as i64;
+    if v >= MAX_EXACT_INT {
+        format!(">= {}", MAX_EXACT_INT)
+    } else if v <= -MAX_EXACT_INT {
+        format!("<= {}", -MAX_EXACT_INT)
+    } else {
+        format!("{}", v)
+    }
+}
+
+/// On/off strip, oldest value on the left
+fn strip_ui(ui: &mut Ui, channel: &DebugChannel) {
+    let width = ui.available_width();
+    let (response, painter) = ui.allocate_painter(vec2(width, STRIP_HEIGHT), Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+    let step = rect.width() / channel.data.data.len() as f32;
+    let color = ui.visuals().selection.bg_fill;
+    for (n, v) in channel.data.iter().enumerate() {
+        if *v > 0.5 {
+            let left = rect.left() + n as f32 * step;
+            painter.rect_filled(
+                Rect::from_min_max(pos2(left, rect.top()), pos2(left + step, rect.bottom())),
+                0.0,
+                color,
+            );
+        }
+    }
+}
+
+/// Bar for each value of the latest array, scaled to the largest magnitude
+fn bar_chart_ui(ui: &mut Ui, channel: &DebugChannel) {
+    let width = ui.available_width();
+    let (response, painter) = ui.allocate_painter(vec2(width, BAR_CHART_HEIGHT), Sense::hover());
+    let rect = response.rect;
+    painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+    let max = channel.data.iter().fold(
+        0.0f32,
+        |max, v| if v.is_finite() { max.max(v.abs()) } else { max },
+    );
+    let negative = channel.data.iter().any(|v| *v < 0.0);
+    let zero_y = if negative {
+        rect.center().y
+    } else {
+        rect.bottom()
+    };
+    let scale = if max > 0.0 {
+        (zero_y - rect.top()) / max
+    } else {
+        0.0
+    };
+    let step = rect.width() / channel.data.data.len() as f32;
+    let color = ui.visuals().selection.bg_fill;
+    for (n, v) in channel.data.iter().enumerate() {
+        if !v.is_finite() {
+            continue;
+        }
+        let left = rect.left() + n as f32 * step;
+        let y = zero_y - v * scale;
+        painter.rect_filled(
+            Rect::from_two_pos(pos2(left, zero_y), pos2(left + (step - 1.0).max(1.0), y)),
+            0.0,
+            color,
+        );
+    }
+    painter.line_segment(
+        [pos2(rect.left(), zero_y), pos2(rect.right(), zero_y)],
+        Stroke::new(1.0, ui.visuals().widgets.noninteractive.bg_stroke.color),
+    );
+    if let Some(pos) = response.hover_pos() {
+        let n = ((pos.x - rect.left()) / step) as usize;
+        if let Some(v) = channel.data.data.get(n) {
+            egui::show_tooltip_text(
+                ui.ctx(),
+                egui::Id::new("debug_bar"),
+                format!("[{}] {:.6}", n, v),
+            );
+        }
+    }
+}
+
+fn spectrum_channel_ui(ui: &mut Ui, channel: &mut DebugChannel, sample_rate: f32) {
+    let spectrum = &mut channel.spectrum;
+    ui.add(egui::Slider::new(&mut spectrum.averaging, 0.0..=0.99).text("Averaging"));
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut spectrum.peak_hold, "Peak Hold");
+        if ui.button("Reset Peaks").clicked() {
+            spectrum.reset_peaks();
+        }
+    });
+    spectrum.update(channel.data.iter());
+    spectrum_ui(ui, spectrum, sample_rate);
+}
+
+fn waveform_ui(ui: &mut Ui, i: usize, channel: &mut DebugChannel, samples: &[(String, Vec<f32>)]) {
+    let waveform = &mut channel.waveform;
+
+    ui.checkbox(&mut waveform.enable_waveform, "Waveform");
+
+    ui.checkbox(&mut waveform.enable_smoothing, "Smoothing");
+
+    if waveform.enable_waveform {
+        if waveform.enable_smoothing {
+            ui.add(egui::Slider::new(&mut waveform.memory_decay, 0.1..=2.0).text("Memory Decay"));
+
+            ui.add(egui::Slider::new(&mut waveform.display_decay, 0.1..=2.0).text("Display Decay"));
+
+            let max_size = channel.data.data.len().max(MIN_WAVEFORM_SIZE);
+            ui.add(
+                egui::Slider::new(&mut waveform.display_size, MIN_WAVEFORM_SIZE..=max_size)
+                    .text("Length"),
+            );
+            ui.add(
+                egui::Slider::new(&mut waveform.match_interval, 0..=max_size)
+                    .text("Match Interval"),
+            );
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source(("weighting", i))
+                    .selected_text(waveform.window.name())
+                    .show_ui(ui, |ui| {
+                        for window in WeightWindow::ALL.iter() {
+                            ui.selectable_value(&mut waveform.window, *window, window.name());
+                        }
+                    });
+                ui.label("Weighting");
+            });
+            aligned_ui(ui, i, &channel.name, &mut waveform.aligned, samples);
+
+            let trigger = channel.data.iter().copied().collect::<Vec<f32>>();
+            let aligned: Vec<(&str, &[f32])> = waveform
+                .aligned
+                .iter()
+                .filter_map(|name| samples.iter().find(|(n, _)| n == name))
+                .map(|(name, data)| (name.as_str(), data.as_slice()))
+                .collect();
+            let aligned_data: Vec<&[f32]> = aligned.iter().map(|(_, data)| *data).collect();
+            waveform.update(&trigger, &aligned_data, channel.new_values);
+
+            let mut plot = Plot::new(format!("debug{}", i))
+                .view_aspect(1.0)
+                .allow_drag(false)
+                .allow_zoom(false)
+                .show_x(false)
+                .show_axes([false, true]);
+            let names =
+                std::iter::once(channel.name.as_str()).chain(aligned.iter().map(|(name, _)| *name));
+            for (n, name) in names.enumerate() {
+                let data = waveform.channel_display(n);
+                plot = plot.line(
+                    Line::new(Values::from_values_iter(
+                        data.iter()
+                            .enumerate()
+                            .map(|(i, v)| Value::new(i as f32, *v)),
+                    ))
+                    .name(name),
+                );
+            }
+            if !aligned.is_empty() {
+                plot = plot.legend(Legend::default());
+            }
+            ui.add(plot);
+        } else {
+            let data = channel.data.iter();
+            let line = Line::new(Values::from_values_iter(
+                data.enumerate().map(|(i, v)| Value::new(i as f32, *v)),
+            ));
+            ui.add(
+                Plot::new(format!("debug{}", i))
+                    .line(line)
+                    .view_aspect(1.0)
+                    .allow_drag(false)
+                    .allow_zoom(false)
+                    .show_x(false)
+                    .show_axes([false, true]),
+            );
+        };
+    }
+}
+
+/// Pick the sample channels that are shifted along with this one, so they line up with it
+fn aligned_ui(
+    ui: &mut Ui,
+    i: usize,
+    name: &str,
+    aligned: &mut Vec<String>,
+    samples: &[(String, Vec<f32>)],
+) {
+    egui::CollapsingHeader::new("Align Channels")
+        .id_source(("align", i))
+        .show(ui, |ui| {
+            for (other, _) in samples.iter().filter(|(other, _)| other != name) {
+                let mut on = aligned.contains(other);
+                if ui.checkbox(&mut on, other).changed() {
+                    if on {
+                        aligned.push(other.clone());
                     } else {
-                        let data = debug_out.consumers[i].iter();
-                        let line = Line::new(Values::from_values_iter(
-                            data.enumerate().map(|(i, v)| Value::new(i as f32, *v)),
-                        ));
-                        ui.add(
-                            Plot::new(format!("debug{}", i))
-                                .line(line)
-                                .view_aspect(1.0)
-                                .allow_drag(false)
-                                .allow_zoom(false)
-                                .show_x(false)
-                                .show_axes([false, true]),
-                        );
-                    };
-
-                    ui.separator();
+                        aligned.retain(|a| a != other);
+                    }
                 }
             }
         });
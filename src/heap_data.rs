@@ -22,6 +22,10 @@ impl Heap {
     pub fn get_ptr(&self) -> *mut u8 {
         self.ptr
     }
+
+    pub fn size(&self) -> usize {
+        self.layout.size()
+    }
 }
 
 unsafe impl Send for Heap {}
@@ -8,44 +8,63 @@ use float_id::FloatId;
 use log::error;
 use preset_manager::Projects;
 use raw_window_handle::HasRawWindowHandle;
-use ringbuf::RingBuffer;
-use sarus_egui_lib::{DebuggerInput, DebuggerOutput};
+use sarus_egui_lib::normalized_slider_ui;
 use serde::{Deserialize, Serialize};
+use shared_data::{new_shared_data, DSPSharedData, DSPSharedRef, UISharedData, UISharedRef};
+use state_inspector::{StateLayout, StateSnapshot};
+use text_arena::{TextArena, TextArenaRef};
 
 use egui::{Align, CtxRef, Direction, Layout};
 use egui_baseview::{EguiWindow, Queue, RenderSettings, Settings};
 use triple_buffer::{Output, TripleBuffer};
-use units::ConsumerRingBuf;
 
 use std::{
     cell::RefCell,
     sync::{
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicBool, AtomicI16, Ordering},
         Mutex,
     },
 };
 
 use compiler::{AudioData, CompiledDSPPayload, CompiledUIPayload};
-use compiler_interface::setup_fonts;
+use console::LogSource;
+use debugger::{new_debugger, DebuggerInput};
 
 pub mod atomic_f32;
 pub mod code_editor;
 pub mod compiler;
 pub mod compiler_interface;
+pub mod console;
 pub mod correlation_match;
+pub mod curve_editor;
+pub mod debug_layout;
+pub mod debugger;
 pub mod float_id;
+pub mod freeze;
+pub mod goniometer;
 pub mod graphs;
 pub mod heap_data;
 pub mod logging;
+pub mod meters;
 pub mod preset_manager;
+pub mod recorder;
+pub mod shared_data;
 pub mod units;
 
 use logging::init_logging;
 
 use std::sync::Arc;
 
+pub mod pitch;
 pub mod sarus_egui_lib;
+pub mod scope;
+pub mod shortcuts;
+pub mod spectrum;
+pub mod state_inspector;
+pub mod stats;
 pub mod syntax_highlighting;
+pub mod text_arena;
+pub mod theme;
 
 baseplug::model! {
     #[derive(Debug, Serialize, Deserialize)]
@@ -145,6 +164,17 @@ baseplug::model! {
             gradient = "Linear")]
         pub id2: f32,
 
+        // Parameters like the project id so the window size is saved by VST hosts
+        #[model(min = 0.0, max = 4096.0)]
+        #[parameter(name = "UI Width", unit = "Generic", smoothing = false,
+            gradient = "Linear")]
+        pub ui_width: f32,
+
+        #[model(min = 0.0, max = 4096.0)]
+        #[parameter(name = "UI Height", unit = "Generic", smoothing = false,
+            gradient = "Linear")]
+        pub ui_height: f32,
+
     }
 }
 
@@ -173,10 +203,52 @@ impl Default for SarusPluginModel {
             gain_master: 1.0,
             id1: 0.0,
             id2: 0.0,
+            // 0.0 uses the size declared by the project
+            ui_width: 0.0,
+            ui_height: 0.0,
         }
     }
 }
 
+pub const MIN_UI_SIZE: i16 = 200;
+pub const MAX_UI_SIZE: i16 = 4096;
+
+// ui_size() has no access to the plugin instance, so it reports the size of the instance that
+// last loaded its state or changed its size. Each window is still opened at the size stored in
+// its own instance, see `window_size`.
+static UI_SIZE_HINT: [AtomicI16; 2] = [AtomicI16::new(700), AtomicI16::new(700)];
+
+fn clamp_ui_size(width: f32, height: f32) -> (i16, i16) {
+    let clamp = |x: f32| (x.round() as i16).clamp(MIN_UI_SIZE, MAX_UI_SIZE);
+    (clamp(width), clamp(height))
+}
+
+/// Size stored in the UI Width and UI Height params, None if it's 0 and the size declared by
+/// the theme of the loaded project is used
+fn stored_window_size(width: f32, height: f32) -> Option<(i16, i16)> {
+    if width > 0.0 && height > 0.0 {
+        Some(clamp_ui_size(width, height))
+    } else {
+        None
+    }
+}
+
+/// Size of the plugin window of an instance
+fn window_size(shared_ctx: &SarusSharedState, width: f32, height: f32) -> (i16, i16) {
+    let size = stored_window_size(width, height).unwrap_or_else(|| {
+        let projects = shared_ctx.projects.lock().unwrap();
+        let theme = projects.get_theme(shared_ctx.project_float_id.get_u64());
+        clamp_ui_size(theme.window_size[0], theme.window_size[1])
+    });
+    set_ui_size_hint(size)
+}
+
+fn set_ui_size_hint(size: (i16, i16)) -> (i16, i16) {
+    UI_SIZE_HINT[0].store(size.0, Ordering::Relaxed);
+    UI_SIZE_HINT[1].store(size.1, Ordering::Relaxed);
+    size
+}
+
 //extern "C"  {
 //    fn sarus_ui(ui: &mut Ui, data:&mut [f32; 4]);
 //}
@@ -187,9 +259,17 @@ pub struct SarusSharedState {
     ui_payload_out: Arc<Mutex<Output<Option<CompiledUIPayload>>>>,
     dsp_payload_out: Arc<RefCell<Output<Option<CompiledDSPPayload>>>>,
     debug_in: Arc<RefCell<DebuggerInput>>,
+    ui_shared: Arc<Mutex<UISharedData>>,
+    dsp_shared: Arc<RefCell<DSPSharedData>>,
     project_float_id: FloatId,
     audio_thread_float_id: FloatId,
     projects: Arc<Mutex<Projects>>,
+    /// Layout of the `ProcessState` of the last compile, None if it couldn't be read
+    process_layout: Arc<Mutex<Option<StateLayout>>>,
+    state_snapshot: Arc<Mutex<StateSnapshot>>,
+    /// Set each frame when a widget in the plugin window has keyboard focus. Key events from the
+    /// host are only consumed while it's set, the rest are passed back to the host.
+    ui_wants_keyboard: AtomicBool,
 }
 
 unsafe impl Send for SarusSharedState {}
@@ -218,13 +298,10 @@ impl PluginContext<SarusPlugin> for SarusPluginShared {
         let ui_payload_out = Arc::new(Mutex::new(ui_payload_out));
         let dsp_payload_out = Arc::new(RefCell::new(dsp_payload_out));
 
-        let mut producers = Vec::new();
-        let mut consumers = Vec::new();
-        for _ in 0..4 {
-            let (prod, cons) = RingBuffer::<f32>::new(1024).split();
-            producers.push(prod);
-            consumers.push(ConsumerRingBuf::new(cons, 1024));
-        }
+        let (debug_in, mut debug_out) = new_debugger();
+
+        let editor_console = debug_out.console.new_input(LogSource::Editor);
+        let (ui_shared, dsp_shared) = new_shared_data(editor_console);
 
         let projects = Arc::new(Mutex::new(Projects::load().unwrap()));
         let project_float_id = FloatId::from_f32s(0.0, 0.0);
@@ -235,16 +312,21 @@ impl PluginContext<SarusPlugin> for SarusPluginShared {
             trigger_compile,
             ui_payload_out,
             dsp_payload_out,
-            debug_in: Arc::new(RefCell::new(DebuggerInput { producers })),
+            debug_in: Arc::new(RefCell::new(debug_in)),
+            ui_shared: Arc::new(Mutex::new(ui_shared)),
+            dsp_shared: Arc::new(RefCell::new(dsp_shared)),
             project_float_id,
             audio_thread_float_id,
             projects,
+            process_layout: Arc::new(Mutex::new(None)),
+            state_snapshot: Arc::new(Mutex::new(StateSnapshot::default())),
+            ui_wants_keyboard: AtomicBool::new(false),
         });
 
         compiler_interface::init_compiler_editor_thread(
             ui_payload_in,
             dsp_payload_in,
-            DebuggerOutput { consumers },
+            debug_out,
             shared_ctx.clone(),
         );
 
@@ -275,6 +357,9 @@ impl Plugin for SarusPlugin {
             .shared_ctx
             .project_float_id
             .update_from_f32(model.id1, model.id2);
+        if let Some(size) = stored_window_size(model.ui_width, model.ui_height) {
+            set_ui_size_hint(size);
+        }
         Self {
             sample_rate,
             last_id1: model.id1,
@@ -294,6 +379,8 @@ impl Plugin for SarusPlugin {
         let dsp_payload = dsp_payload_borrow.read();
 
         let mut debug_in_borrow = shared_ctx.debug_in.borrow_mut();
+        debug_in_borrow.update(self.sample_rate);
+        let mut dsp_shared_borrow = shared_ctx.dsp_shared.borrow_mut();
 
         //TODO it seems like there is still smoothing
         if model.id1[ctx.nframes - 1] == model.id1[0] {
@@ -311,6 +398,7 @@ impl Plugin for SarusPlugin {
         let input = &ctx.inputs[0].buffers;
         let output = &mut ctx.outputs[0].buffers;
         if let Some(dsp_payload) = dsp_payload {
+            dsp_shared_borrow.set_generation(dsp_payload.shared_generation);
             let mut sarus_params = SarusDSPModelParams::from_dsp_model(model);
             let mut audio_data = AudioData {
                 in_left: input[0].as_ptr(),
@@ -319,6 +407,9 @@ impl Plugin for SarusPlugin {
                 out_right: output[1].as_mut_ptr(),
                 len: ctx.nframes as i64,
                 sample_rate: self.sample_rate,
+                shared: DSPSharedRef {
+                    ptr: &mut *dsp_shared_borrow,
+                },
             };
             (dsp_payload.process_func)(
                 &mut sarus_params,
@@ -326,6 +417,10 @@ impl Plugin for SarusPlugin {
                 dsp_payload.process_data.get_ptr(),
                 &mut debug_in_borrow,
             );
+            dsp_shared_borrow.publish();
+            if let Ok(mut snapshot) = shared_ctx.state_snapshot.try_lock() {
+                snapshot.capture(&dsp_payload.process_data);
+            }
 
             for i in 0..ctx.nframes {
                 output[0][i] = output[0][i] * model.gain_master[i];
@@ -337,6 +432,18 @@ impl Plugin for SarusPlugin {
                 output[1][i] = input[1][i] * model.gain_master[i];
             }
         }
+        let n = ctx.nframes;
+        debug_in_borrow.check_output([&output[0][..n], &output[1][..n]]);
+        debug_in_borrow.tap_plugin_io(
+            [&input[0][..n], &input[1][..n]],
+            [&output[0][..n], &output[1][..n]],
+        );
+        if debug_in_borrow.muted() {
+            for i in 0..n {
+                output[0][i] = 0.0;
+                output[1][i] = 0.0;
+            }
+        }
     }
 }
 
@@ -344,6 +451,7 @@ pub fn param_slider(
     ui: &mut egui::Ui,
     label: &str,
     param: &mut UIFloatParam<SarusPluginModel, SarusPluginModelSmooth>,
+    default_unit_value: f32,
 ) {
     ui.label(label);
 
@@ -351,35 +459,50 @@ pub fn param_slider(
     //
     // You could opt to use your own custom widget if you wish, as long as it can operate with
     // a normalized range from [0.0, 1.0].
-    let mut normal = param.normalized();
-    if ui
-        .add(
-            egui::Slider::new(&mut normal, 0.0..=1.0)
-                .show_value(false)
-                .text(format!("{:.1} {}", param.unit_value(), param.unit_label())),
-        )
-        .changed()
-    {
-        param.set_from_normalized(normal);
-    };
+    let normal = param.normalized();
+    let (new_normal, response) = normalized_slider_ui(
+        ui,
+        format!("{:.1} {}", param.unit_value(), param.unit_label()),
+        normal,
+        None,
+    );
+    if response.drag_started() || response.double_clicked() {
+        param.begin_edit();
+    }
+    if response.double_clicked() {
+        param.set_from_unit_value(default_unit_value);
+    } else if new_normal != normal {
+        param.set_from_normalized(new_normal);
+    }
+    if response.drag_released() || response.double_clicked() {
+        param.end_edit();
+    }
 }
 
 impl baseplug::PluginUI for SarusPlugin {
     type Handle = ();
 
     fn ui_size() -> (i16, i16) {
-        (700, 700)
+        (
+            UI_SIZE_HINT[0].load(Ordering::Relaxed),
+            UI_SIZE_HINT[1].load(Ordering::Relaxed),
+        )
     }
 
     fn ui_open(
         parent: &impl HasRawWindowHandle,
         shared_ctx: &SarusPluginShared,
-        model: <Self::Model as Model<Self>>::UI,
+        mut model: <Self::Model as Model<Self>>::UI,
     ) -> WindowOpenResult<Self::Handle> {
+        let (width, height) = window_size(
+            &shared_ctx.shared_ctx,
+            model.ui_width.unit_value(),
+            model.ui_height.unit_value(),
+        );
         let settings = Settings {
             window: WindowOpenOptions {
                 title: String::from("egui-baseplug-examples gain"),
-                size: Size::new(Self::ui_size().0 as f64, Self::ui_size().1 as f64),
+                size: Size::new(width as f64, height as f64),
                 scale: WindowScalePolicy::SystemScaleFactor,
             },
             render_settings: RenderSettings::default(),
@@ -389,23 +512,41 @@ impl baseplug::PluginUI for SarusPlugin {
             parent,
             settings,
             PluginEditorState {
+                last_params: ui_params(&mut model),
                 model_state: model,
                 shared_ctx: shared_ctx.shared_ctx.clone(),
+                text_arena: TextArena::default(),
+                theme_project_id: 0,
+                window_size: (width, height),
             },
             // Called once before the first frame. Allows you to do setup code and to
             // call `ctx.set_fonts()`. Optional.
-            |ctx: &CtxRef, _queue: &mut Queue, _editor_state: &mut PluginEditorState| {
-                setup_fonts(ctx);
-                let mut style: egui::Style = (*ctx.style()).clone();
-                style.spacing.interact_size = egui::vec2(40.0, 40.0);
-                style.spacing.slider_width = 300.0;
-                ctx.set_style(style);
-            },
+            |_ctx: &CtxRef, _queue: &mut Queue, _editor_state: &mut PluginEditorState| {},
             // Called before each frame. Here you should update the state of your
             // application and build the UI.
             |ctx: &CtxRef, _queue: &mut Queue, editor_state: &mut PluginEditorState| {
                 // Must be called on the top of each frame in order to sync values from the rt thread.
 
+                let current_id = editor_state.shared_ctx.project_float_id.get_u64();
+                if editor_state.theme_project_id != current_id {
+                    if let Ok(projects) = editor_state.shared_ctx.projects.try_lock() {
+                        let theme = projects.get_theme(current_id);
+                        theme.apply(ctx, &projects.project_paths.projects_dir);
+                        let model_state = &editor_state.model_state;
+                        let stored = stored_window_size(
+                            model_state.ui_width.unit_value(),
+                            model_state.ui_height.unit_value(),
+                        );
+                        if stored.is_none() {
+                            editor_state.window_size = set_ui_size_hint(clamp_ui_size(
+                                theme.window_size[0],
+                                theme.window_size[1],
+                            ));
+                        }
+                        editor_state.theme_project_id = current_id;
+                    }
+                }
+
                 egui::CentralPanel::default().show(ctx, |ui| {
                     let layout =
                         Layout::from_main_dir_and_cross_align(Direction::TopDown, Align::LEFT)
@@ -426,6 +567,8 @@ impl baseplug::PluginUI for SarusPlugin {
                                 if let Err(e) = projects.reload() {
                                     error!("Could not reload {}", e);
                                 }
+                                // Re-apply the theme in case it was changed in the config
+                                editor_state.theme_project_id = 0;
                             }
 
                             let name = projects.get_name_from_id(current_id).unwrap_or("");
@@ -449,22 +592,15 @@ impl baseplug::PluginUI for SarusPlugin {
                                     .project_float_id
                                     .update_from_f32(f1, f2);
                                 //TODO let Sarus code define defaults
-                                editor_state.model_state.param1.set_from_unit_value(0.5);
-                                editor_state.model_state.param2.set_from_unit_value(0.5);
-                                editor_state.model_state.param3.set_from_unit_value(0.5);
-                                editor_state.model_state.param4.set_from_unit_value(0.5);
-                                editor_state.model_state.param5.set_from_unit_value(0.5);
-                                editor_state.model_state.param6.set_from_unit_value(0.5);
-                                editor_state.model_state.param7.set_from_unit_value(0.5);
-                                editor_state.model_state.param8.set_from_unit_value(0.5);
-                                editor_state.model_state.param9.set_from_unit_value(0.5);
-                                editor_state.model_state.param10.set_from_unit_value(0.5);
-                                editor_state.model_state.param11.set_from_unit_value(0.5);
-                                editor_state.model_state.param12.set_from_unit_value(0.5);
-                                editor_state.model_state.param13.set_from_unit_value(0.5);
-                                editor_state.model_state.param14.set_from_unit_value(0.5);
-                                editor_state.model_state.param15.set_from_unit_value(0.5);
-                                editor_state.model_state.param16.set_from_unit_value(0.5);
+                                let model_state = &mut editor_state.model_state;
+                                for i in 0..16 {
+                                    ui_param_mut(model_state, i).set_from_unit_value(0.5);
+                                }
+                                // The reset comes from the UI, don't report it as automation
+                                editor_state.last_params = ui_params(model_state);
+                                // Use the window size declared by the new project
+                                editor_state.model_state.ui_width.set_from_unit_value(0.0);
+                                editor_state.model_state.ui_height.set_from_unit_value(0.0);
                             }
                         }
                         if ui.button("Open Editor").clicked() {
@@ -480,6 +616,31 @@ impl baseplug::PluginUI for SarusPlugin {
                                 .trigger_compile
                                 .store(true, Ordering::Relaxed);
                         }
+                        ui.collapsing("Window Size", |ui| {
+                            let (mut width, mut height) = editor_state.window_size;
+                            let mut changed = false;
+                            for (label, value) in [("Width", &mut width), ("Height", &mut height)] {
+                                ui.horizontal(|ui| {
+                                    changed |= ui
+                                        .add(
+                                            egui::DragValue::new(value)
+                                                .clamp_range(MIN_UI_SIZE..=MAX_UI_SIZE)
+                                                .suffix(" px"),
+                                        )
+                                        .changed();
+                                    ui.label(label);
+                                });
+                            }
+                            if changed {
+                                let model_state = &mut editor_state.model_state;
+                                model_state.ui_width.set_from_unit_value(width as f32);
+                                model_state.ui_height.set_from_unit_value(height as f32);
+                                editor_state.window_size = set_ui_size_hint((width, height));
+                            }
+                            // TODO: Resize the open window once baseview gets the ability to do
+                            // this, and pick up resizes made by the host.
+                            ui.label("The new size is used when the window is reopened");
+                        });
                         ui.separator();
 
                         if let Some(compiled_payload) = editor_state
@@ -489,17 +650,36 @@ impl baseplug::PluginUI for SarusPlugin {
                             .unwrap()
                             .read()
                         {
-                            let mut sarus_params =
-                                SarusUIModelParams::from_ui_model(&editor_state.model_state);
+                            let mut ui_shared = editor_state.shared_ctx.ui_shared.lock().unwrap();
+                            ui_shared.set_generation(compiled_payload.shared_generation);
+                            ui_shared.update();
+                            editor_state.text_arena.clear();
+                            let params = ui_params(&mut editor_state.model_state);
+                            let mut changed = [false; 16];
+                            for (i, changed) in changed.iter_mut().enumerate() {
+                                *changed = params[i] != editor_state.last_params[i];
+                            }
+                            let mut sarus_params = SarusUIModelParams::from_ui_model(
+                                &editor_state.model_state,
+                                &mut ui_shared,
+                                &mut editor_state.text_arena,
+                                changed,
+                            );
                             (compiled_payload.editor_func)(
                                 ui,
                                 &mut sarus_params,
                                 compiled_payload.editor_data.get_ptr(),
                             );
                             sarus_params.to_model(&mut editor_state.model_state);
+                            editor_state.last_params = ui_params(&mut editor_state.model_state);
                         }
                         ui.separator();
-                        param_slider(ui, "Gain Master", &mut editor_state.model_state.gain_master);
+                        param_slider(
+                            ui,
+                            "Gain Master",
+                            &mut editor_state.model_state.gain_master,
+                            0.0,
+                        );
                     });
                 });
 
@@ -516,6 +696,10 @@ impl baseplug::PluginUI for SarusPlugin {
                     editor_state.model_state.id2.set_from_unit_value(f2);
                 }
 
+                editor_state
+                    .shared_ctx
+                    .ui_wants_keyboard
+                    .store(ctx.wants_keyboard_input(), Ordering::Relaxed);
                 ctx.request_repaint();
             },
         );
@@ -527,12 +711,18 @@ impl baseplug::PluginUI for SarusPlugin {
         // TODO: Close window once baseview gets the ability to do this.
     }
 
-    fn ui_key_down(_plug_ctx: &Self::PluginContext, _ev: keyboard_types::KeyboardEvent) -> bool {
-        true
+    fn ui_key_down(plug_ctx: &Self::PluginContext, _ev: keyboard_types::KeyboardEvent) -> bool {
+        plug_ctx
+            .shared_ctx
+            .ui_wants_keyboard
+            .load(Ordering::Relaxed)
     }
 
-    fn ui_key_up(_plug_ctx: &Self::PluginContext, _ev: keyboard_types::KeyboardEvent) -> bool {
-        true
+    fn ui_key_up(plug_ctx: &Self::PluginContext, _ev: keyboard_types::KeyboardEvent) -> bool {
+        plug_ctx
+            .shared_ctx
+            .ui_wants_keyboard
+            .load(Ordering::Relaxed)
     }
 
     fn ui_param_notify(
@@ -544,12 +734,54 @@ impl baseplug::PluginUI for SarusPlugin {
         >,
         _val: f32,
     ) {
+        // The handle can't reach the editor state, host changes are instead picked up by
+        // comparing the param values each frame. Every write made by the UI itself has to update
+        // `PluginEditorState::last_params` so it isn't mistaken for one.
     }
 }
 
+fn ui_param_mut(
+    model: &mut SarusPluginModelUI<SarusPlugin>,
+    index: usize,
+) -> &mut UIFloatParam<SarusPluginModel, SarusPluginModelSmooth> {
+    match index {
+        0 => &mut model.param1,
+        1 => &mut model.param2,
+        2 => &mut model.param3,
+        3 => &mut model.param4,
+        4 => &mut model.param5,
+        5 => &mut model.param6,
+        6 => &mut model.param7,
+        7 => &mut model.param8,
+        8 => &mut model.param9,
+        9 => &mut model.param10,
+        10 => &mut model.param11,
+        11 => &mut model.param12,
+        12 => &mut model.param13,
+        13 => &mut model.param14,
+        14 => &mut model.param15,
+        _ => &mut model.param16,
+    }
+}
+
+fn ui_params(model: &mut SarusPluginModelUI<SarusPlugin>) -> [f32; 16] {
+    let mut params = [0.0; 16];
+    for (i, param) in params.iter_mut().enumerate() {
+        *param = ui_param_mut(model, i).normalized();
+    }
+    params
+}
+
 pub struct PluginEditorState {
     model_state: SarusPluginModelUI<SarusPlugin>,
     shared_ctx: Arc<SarusSharedState>,
+    text_arena: TextArena,
+    theme_project_id: u64,
+    // Size of this instance's window, resolved from the plugin state or the project theme
+    window_size: (i16, i16),
+    // Normalized param values as they were last written or seen by the UI, used to detect
+    // changes coming from the host
+    last_params: [f32; 16],
 }
 
 //TODO try to get sarus to be able to take the whole model directly
@@ -572,10 +804,37 @@ pub struct SarusUIModelParams {
     pub param14: f32,
     pub param15: f32,
     pub param16: f32,
+    pub shared: UISharedRef,
+    pub text: TextArenaRef,
+    // Set when the param was changed by the host since the last frame
+    pub p1_changed: bool,
+    pub p2_changed: bool,
+    pub p3_changed: bool,
+    pub p4_changed: bool,
+    pub p5_changed: bool,
+    pub p6_changed: bool,
+    pub p7_changed: bool,
+    pub p8_changed: bool,
+    pub p9_changed: bool,
+    pub p10_changed: bool,
+    pub p11_changed: bool,
+    pub p12_changed: bool,
+    pub p13_changed: bool,
+    pub p14_changed: bool,
+    pub p15_changed: bool,
+    pub p16_changed: bool,
+    // Bit per param, set by widgets that are bound to a parameter to begin or end a host gesture
+    pub gesture_begin: i64,
+    pub gesture_end: i64,
 }
 
 impl SarusUIModelParams {
-    fn from_ui_model(model: &SarusPluginModelUI<SarusPlugin>) -> Self {
+    fn from_ui_model(
+        model: &SarusPluginModelUI<SarusPlugin>,
+        shared: &mut UISharedData,
+        text: &mut TextArena,
+        changed: [bool; 16],
+    ) -> Self {
         SarusUIModelParams {
             param1: model.param1.normalized(),
             param2: model.param2.normalized(),
@@ -593,9 +852,61 @@ impl SarusUIModelParams {
             param14: model.param14.normalized(),
             param15: model.param15.normalized(),
             param16: model.param16.normalized(),
+            shared: UISharedRef { ptr: shared },
+            text: TextArenaRef { ptr: text },
+            p1_changed: changed[0],
+            p2_changed: changed[1],
+            p3_changed: changed[2],
+            p4_changed: changed[3],
+            p5_changed: changed[4],
+            p6_changed: changed[5],
+            p7_changed: changed[6],
+            p8_changed: changed[7],
+            p9_changed: changed[8],
+            p10_changed: changed[9],
+            p11_changed: changed[10],
+            p12_changed: changed[11],
+            p13_changed: changed[12],
+            p14_changed: changed[13],
+            p15_changed: changed[14],
+            p16_changed: changed[15],
+            gesture_begin: 0,
+            gesture_end: 0,
+        }
+    }
+    pub fn param_mut(&mut self, index: usize) -> Option<&mut f32> {
+        match index {
+            0 => Some(&mut self.param1),
+            1 => Some(&mut self.param2),
+            2 => Some(&mut self.param3),
+            3 => Some(&mut self.param4),
+            4 => Some(&mut self.param5),
+            5 => Some(&mut self.param6),
+            6 => Some(&mut self.param7),
+            7 => Some(&mut self.param8),
+            8 => Some(&mut self.param9),
+            9 => Some(&mut self.param10),
+            10 => Some(&mut self.param11),
+            11 => Some(&mut self.param12),
+            12 => Some(&mut self.param13),
+            13 => Some(&mut self.param14),
+            14 => Some(&mut self.param15),
+            15 => Some(&mut self.param16),
+            _ => None,
         }
     }
+    pub fn begin_gesture(&mut self, index: usize) {
+        self.gesture_begin |= 1 << index;
+    }
+    pub fn end_gesture(&mut self, index: usize) {
+        self.gesture_end |= 1 << index;
+    }
     fn to_model(&self, model: &mut SarusPluginModelUI<SarusPlugin>) {
+        for index in 0..16 {
+            if self.gesture_begin & (1 << index) != 0 {
+                ui_param_mut(model, index).begin_edit();
+            }
+        }
         model.param1.set_from_normalized(self.param1);
         model.param2.set_from_normalized(self.param2);
         model.param3.set_from_normalized(self.param3);
@@ -612,6 +923,11 @@ impl SarusUIModelParams {
         model.param14.set_from_normalized(self.param14);
         model.param15.set_from_normalized(self.param15);
         model.param16.set_from_normalized(self.param16);
+        for index in 0..16 {
+            if self.gesture_end & (1 << index) != 0 {
+                ui_param_mut(model, index).end_edit();
+            }
+        }
     }
 }
 
@@ -695,3 +1011,18 @@ impl SarusDSPModelParams {
 
 #[cfg(not(test))]
 baseplug::vst2!(SarusPlugin, b"SaRu");
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_window_size_save_load() {
+        // Hosts save the params normalized over the 0..4096 range of the model
+        let save_load = |x: f32| (x / 4096.0) * 4096.0;
+        for size in [(MIN_UI_SIZE, 333), (700, 1234), (2999, MAX_UI_SIZE)] {
+            let (width, height) = (save_load(size.0 as f32), save_load(size.1 as f32));
+            assert_eq!(stored_window_size(width, height), Some(size));
+        }
+        assert_eq!(stored_window_size(save_load(0.0), save_load(0.0)), None);
+    }
+}
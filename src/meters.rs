@@ -0,0 +1,170 @@
+use egui::{remap_clamp, vec2, Align2, Color32, Id, Rect, Response, Sense, Stroke, TextStyle, Ui};
+
+use crate::sarus_egui_lib::lin_to_db;
+
+pub const METER_MIN_DB: f32 = -60.0;
+pub const METER_MAX_DB: f32 = 6.0;
+pub const GAIN_REDUCTION_RANGE_DB: f32 = 24.0;
+
+const METER_HEIGHT: f32 = 16.0;
+const HOLD_TIME: f64 = 1.5;
+const DECAY_DB_PER_SECOND: f32 = 24.0;
+
+/// Display state of a meter, kept in egui memory between frames.
+#[derive(Clone, Copy)]
+struct MeterState {
+    peak_db: f32,
+    rms_db: f32,
+    hold_db: f32,
+    hold_start: f64,
+    last_time: f64,
+}
+
+impl MeterState {
+    fn new(time: f64) -> Self {
+        MeterState {
+            peak_db: METER_MIN_DB,
+            rms_db: METER_MIN_DB,
+            hold_db: METER_MIN_DB,
+            hold_start: time,
+            last_time: time,
+        }
+    }
+
+    /// Rise instantly, fall at `DECAY_DB_PER_SECOND`. The hold marker is kept for `HOLD_TIME`
+    /// before it starts falling too.
+    fn update(&mut self, peak_db: f32, rms_db: f32, time: f64) {
+        let fall = DECAY_DB_PER_SECOND * (time - self.last_time).max(0.0) as f32;
+        self.last_time = time;
+        self.peak_db = peak_db.max(self.peak_db - fall);
+        self.rms_db = rms_db.max(self.rms_db - fall);
+        if peak_db >= self.hold_db {
+            self.hold_db = peak_db;
+            self.hold_start = time;
+        } else if time - self.hold_start > HOLD_TIME {
+            self.hold_db = peak_db.max(self.hold_db - fall);
+        }
+    }
+}
+
+fn meter_state(ui: &Ui, id: Id, peak_db: f32, rms_db: f32) -> MeterState {
+    let time = ui.input().time;
+    let mut memory = ui.memory();
+    let state = memory
+        .id_data_temp
+        .get_mut_or_insert_with(id, || MeterState::new(time));
+    state.update(peak_db, rms_db, time);
+    *state
+}
+
+fn level_color(db: f32) -> Color32 {
+    if db > 0.0 {
+        Color32::from_rgb(230, 60, 50)
+    } else if db > -6.0 {
+        Color32::from_rgb(230, 200, 50)
+    } else {
+        Color32::from_rgb(70, 200, 90)
+    }
+}
+
+fn allocate_meter(ui: &mut Ui) -> (Response, egui::Painter) {
+    let width = ui.available_width().min(300.0);
+    ui.allocate_painter(vec2(width, METER_HEIGHT), Sense::hover())
+}
+
+/// Peak/RMS level meter. `peak` and `rms` are linear amplitudes, displayed on a dB scale from
+/// `METER_MIN_DB` to `METER_MAX_DB` with peak hold and decay.
+pub fn level_meter_ui(ui: &mut Ui, label: &str, peak: f32, rms: f32) -> Response {
+    ui.horizontal(|ui| {
+        let id = ui.make_persistent_id(label);
+        let state = meter_state(ui, id, lin_to_db(peak), lin_to_db(rms));
+        let (response, painter) = allocate_meter(ui);
+        let rect = response.rect;
+        let x_from_db = |db: f32| remap_clamp(db, METER_MIN_DB..=METER_MAX_DB, rect.x_range());
+
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        let peak_rect =
+            Rect::from_min_max(rect.min, egui::pos2(x_from_db(state.peak_db), rect.max.y));
+        painter.rect_filled(
+            peak_rect,
+            0.0,
+            level_color(state.peak_db).linear_multiply(0.5),
+        );
+        let rms_rect = Rect::from_min_max(
+            egui::pos2(rect.min.x, rect.center().y - METER_HEIGHT * 0.25),
+            egui::pos2(
+                x_from_db(state.rms_db),
+                rect.center().y + METER_HEIGHT * 0.25,
+            ),
+        );
+        painter.rect_filled(rms_rect, 0.0, level_color(state.rms_db));
+        let hold_x = x_from_db(state.hold_db);
+        painter.line_segment(
+            [
+                egui::pos2(hold_x, rect.min.y),
+                egui::pos2(hold_x, rect.max.y),
+            ],
+            Stroke::new(2.0, level_color(state.hold_db)),
+        );
+        let zero_x = x_from_db(0.0);
+        painter.line_segment(
+            [
+                egui::pos2(zero_x, rect.min.y),
+                egui::pos2(zero_x, rect.max.y),
+            ],
+            ui.visuals().widgets.noninteractive.bg_stroke,
+        );
+        painter.text(
+            rect.right_center() - vec2(4.0, 0.0),
+            Align2::RIGHT_CENTER,
+            format!("{:.1}", state.hold_db),
+            TextStyle::Small,
+            ui.visuals().text_color(),
+        );
+        ui.label(label);
+        response
+    })
+    .inner
+}
+
+/// Gain reduction meter. `gain_reduction_db` is the amount of reduction in dB, either sign is
+/// accepted. The bar grows from the right over `GAIN_REDUCTION_RANGE_DB`.
+pub fn gain_reduction_meter_ui(ui: &mut Ui, label: &str, gain_reduction_db: f32) -> Response {
+    ui.horizontal(|ui| {
+        let id = ui.make_persistent_id(label);
+        // Reuse the peak meter ballistics on the reduction amount so it holds and decays the same
+        let state = meter_state(ui, id, gain_reduction_db.abs(), gain_reduction_db.abs());
+        let (response, painter) = allocate_meter(ui);
+        let rect = response.rect;
+        let x_from_gr = |gr: f32| {
+            remap_clamp(
+                gr,
+                0.0..=GAIN_REDUCTION_RANGE_DB,
+                rect.right()..=rect.left(),
+            )
+        };
+
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        let gr_rect =
+            Rect::from_min_max(egui::pos2(x_from_gr(state.peak_db), rect.min.y), rect.max);
+        painter.rect_filled(gr_rect, 0.0, Color32::from_rgb(230, 140, 40));
+        let hold_x = x_from_gr(state.hold_db);
+        painter.line_segment(
+            [
+                egui::pos2(hold_x, rect.min.y),
+                egui::pos2(hold_x, rect.max.y),
+            ],
+            Stroke::new(2.0, Color32::from_rgb(250, 190, 90)),
+        );
+        painter.text(
+            rect.left_center() + vec2(4.0, 0.0),
+            Align2::LEFT_CENTER,
+            format!("-{:.1}", state.hold_db),
+            TextStyle::Small,
+            ui.visuals().text_color(),
+        );
+        ui.label(label);
+        response
+    })
+    .inner
+}
@@ -0,0 +1,212 @@
+use std::f32::consts::PI;
+
+use crate::correlation_match::CorrelationMatch;
+
+pub const PITCH_TRACKER_SIZE: usize = 4096;
+const PITCH_TRACKER_HOP: usize = 512;
+/// Number of calls to `PitchDetector::period_step` that make up one estimate
+pub const PERIOD_STEPS: usize = CorrelationMatch::COMPUTE_STEPS;
+// Samples between the steps of an estimate of `PitchTracker`
+const PITCH_TRACKER_STEP_INTERVAL: usize = PITCH_TRACKER_HOP / PERIOD_STEPS;
+// Below this RMS the signal is treated as silence
+const SILENCE: f32 = 1e-4;
+const NOTE_NAMES: [&str; 12] = [
+    "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+];
+
+/// Estimates the period of a signal by matching its start against the rest of it, with the same
+/// period estimate `DisplayBuffer` uses for scope stabilization.
+///
+/// The match needs a few periods, so the lowest frequency found is about
+/// `4 * sample_rate / size`.
+pub struct PitchDetector {
+    size: usize,
+    matcher: CorrelationMatch,
+    weight: Vec<f32>,
+}
+
+impl PitchDetector {
+    pub fn new(size: usize) -> Self {
+        let match_size = size / 4;
+        // Hann window, prioritizes the center of the matched piece
+        let weight = (0..match_size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / match_size as f32).cos())
+            .collect();
+        PitchDetector {
+            size,
+            matcher: CorrelationMatch::new(size),
+            weight,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Period in samples of the last `size()` samples, None for silence or if no period was found.
+    /// Doesn't allocate, so it can be used on the audio thread.
+    pub fn period(&mut self, samples: &[f32]) -> Option<f32> {
+        let samples = self.matched_samples(samples)?;
+        let (_offset, period) =
+            self.matcher
+                .compute(samples, &samples[..self.weight.len()], &self.weight);
+        period.filter(|period| *period > 1.0)
+    }
+
+    /// Same as `period`, split into `PERIOD_STEPS` calls so the work can be spread over several
+    /// blocks. `step` counts up from 0 with the same `samples` each time. The first two steps each
+    /// do one of the two cross correlations of `period`, the last one is cheap. Returns Some with
+    /// the estimate once it's done, which is after the last step, or right away for silence.
+    pub fn period_step(&mut self, step: usize, samples: &[f32]) -> Option<Option<f32>> {
+        let samples = match self.matched_samples(samples) {
+            Some(samples) => samples,
+            None => return Some(None),
+        };
+        self.matcher
+            .compute_step(step, samples, &samples[..self.weight.len()], &self.weight)
+            .map(|(_offset, period)| period.filter(|period| *period > 1.0))
+    }
+
+    /// The last `size()` samples, None if they can't have a period
+    fn matched_samples<'a>(&self, samples: &'a [f32]) -> Option<&'a [f32]> {
+        if samples.len() < self.size || self.weight.is_empty() {
+            return None;
+        }
+        let samples = &samples[samples.len() - self.size..];
+        if samples.iter().any(|v| !v.is_finite()) {
+            return None;
+        }
+        let rms = (samples.iter().map(|v| v * v).sum::<f32>() / self.size as f32).sqrt();
+        if rms < SILENCE {
+            return None;
+        }
+        Some(samples)
+    }
+
+    pub fn frequency(&mut self, samples: &[f32], sample_rate: f32) -> Option<f32> {
+        self.period(samples).map(|period| sample_rate / period)
+    }
+}
+
+/// Pitch detection on a stream of samples, for Sarus scripts through `DSPShared.pitch`.
+pub struct PitchTracker {
+    detector: PitchDetector,
+    buffer: Vec<f32>,
+    scratch: Vec<f32>,
+    idx: usize,
+    since_update: usize,
+    // Next step of the estimate running on `scratch`, None when it's done
+    step: Option<usize>,
+    /// Latest period estimate in samples, 0.0 if none was found
+    pub period: f32,
+}
+
+impl PitchTracker {
+    pub fn new() -> Self {
+        PitchTracker {
+            detector: PitchDetector::new(PITCH_TRACKER_SIZE),
+            buffer: vec![0.0; PITCH_TRACKER_SIZE],
+            scratch: vec![0.0; PITCH_TRACKER_SIZE],
+            idx: 0,
+            since_update: 0,
+            step: None,
+            period: 0.0,
+        }
+    }
+
+    /// Add a sample. Every `PITCH_TRACKER_HOP` samples the latest `PITCH_TRACKER_SIZE` are
+    /// taken for a new estimate, which is spread over the next hop in `PERIOD_STEPS` steps.
+    /// Most calls only store the sample, a call that runs a step does one cross correlation of
+    /// `PITCH_TRACKER_SIZE` samples.
+    pub fn push(&mut self, v: f32) {
+        self.buffer[self.idx] = v;
+        self.idx = (self.idx + 1) % self.buffer.len();
+        self.since_update += 1;
+        if self.since_update >= PITCH_TRACKER_HOP {
+            self.since_update = 0;
+            let (newest, oldest) = self.buffer.split_at(self.idx);
+            self.scratch[..oldest.len()].copy_from_slice(oldest);
+            self.scratch[oldest.len()..].copy_from_slice(newest);
+            self.step = Some(0);
+        }
+        if let Some(step) = self.step {
+            if self.since_update == step * PITCH_TRACKER_STEP_INTERVAL {
+                match self.detector.period_step(step, &self.scratch) {
+                    Some(period) => {
+                        self.period = period.unwrap_or(0.0);
+                        self.step = None;
+                    }
+                    None => self.step = Some(step + 1),
+                }
+            }
+        }
+    }
+
+    /// Frequency of the latest estimate, 0.0 if none was found
+    pub fn frequency(&self, sample_rate: f32) -> f32 {
+        if self.period > 0.0 {
+            sample_rate / self.period
+        } else {
+            0.0
+        }
+    }
+}
+
+impl Default for PitchTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Nearest note name with octave, like `A4`, and the deviation from it in cents
+pub fn note_name(frequency: f32) -> (String, f32) {
+    let note = 69.0 + 12.0 * (frequency / 440.0).log2();
+    let nearest = note.round();
+    let cents = (note - nearest) * 100.0;
+    let nearest = nearest as i64;
+    let name = NOTE_NAMES[nearest.rem_euclid(12) as usize];
+    (format!("{}{}", name, nearest.div_euclid(12) - 1), cents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_pitch_detection() {
+        let sample_rate = 48000.0;
+        let sine = |freq: f32, len: usize| -> Vec<f32> {
+            (0..len)
+                .map(|i| (2.0 * PI * freq * i as f32 / sample_rate).sin())
+                .collect()
+        };
+        let mut detector = PitchDetector::new(PITCH_TRACKER_SIZE);
+        for freq in [110.0, 440.0, 1234.0].iter() {
+            let detected = detector
+                .frequency(&sine(*freq, PITCH_TRACKER_SIZE), sample_rate)
+                .unwrap();
+            assert!(
+                (detected / freq - 1.0).abs() < 0.01,
+                "{} {}",
+                freq,
+                detected
+            );
+        }
+        assert!(detector.period(&[0.0; PITCH_TRACKER_SIZE]).is_none());
+        let samples = sine(440.0, PITCH_TRACKER_SIZE);
+        let stepped = (0..PERIOD_STEPS)
+            .find_map(|step| detector.period_step(step, &samples))
+            .unwrap();
+        assert_eq!(stepped, detector.period(&samples));
+
+        let mut tracker = PitchTracker::new();
+        for v in sine(440.0, PITCH_TRACKER_SIZE * 2).iter() {
+            tracker.push(*v);
+        }
+        assert!((tracker.frequency(sample_rate) / 440.0 - 1.0).abs() < 0.01);
+
+        assert_eq!(note_name(440.0).0, "A4");
+        let (name, cents) = note_name(261.63 * 1.01);
+        assert_eq!(name, "C4");
+        assert!((cents - 17.2).abs() < 0.5);
+    }
+}
@@ -1,5 +1,8 @@
 use crate::compiler::{DEFAULT_CODE, START_CODE};
+use crate::debug_layout::DebugLayout;
 use crate::float_id::FloatId;
+use crate::shortcuts::Shortcuts;
+use crate::theme::Theme;
 use directories::ProjectDirs;
 use log::trace;
 use std::fs::{self, File};
@@ -14,7 +17,23 @@ use serde::{Deserialize, Serialize};
 pub struct Config {
     pub version: String,
     pub compile_on_load: bool,
+    // Plain values have to come before the tables for the config to serialize to toml
+    #[serde(default = "default_editor_window_size")]
+    pub editor_window_size: [f32; 2],
     pub aliases: HashMap<String, String>,
+    /// Plugin window style keyed by project file name
+    #[serde(default)]
+    pub themes: HashMap<String, Theme>,
+    /// Key bindings of the code editor window
+    #[serde(default)]
+    pub shortcuts: Shortcuts,
+    /// Debug panel settings keyed by project file name
+    #[serde(default)]
+    pub debug_layouts: HashMap<String, DebugLayout>,
+}
+
+fn default_editor_window_size() -> [f32; 2] {
+    [1800.0, 1600.0]
 }
 
 //toml::from_str(&body).unwrap()
@@ -57,7 +76,11 @@ pub fn setup_dirs() -> anyhow::Result<ProjectPaths> {
             let p = Config {
                 version: "0.0.1".to_string(),
                 compile_on_load: false,
+                editor_window_size: default_editor_window_size(),
                 aliases,
+                themes: HashMap::new(),
+                shortcuts: Shortcuts::default(),
+                debug_layouts: HashMap::new(),
             };
             let mut file = File::create(&config_file)?;
             file.write_all(toml::to_string(&p)?.as_bytes())?;
@@ -188,6 +211,21 @@ impl Projects {
         })
     }
 
+    pub fn get_theme(&self, id: u64) -> Theme {
+        self.get_name_from_id(id)
+            .and_then(|name| self.config.themes.get(name))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn get_debug_layout(&self, name: &str) -> DebugLayout {
+        self.config
+            .debug_layouts
+            .get(name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     pub fn get_name_from_id(&self, id: u64) -> Option<&str> {
         if let Some((path, _code)) = self.files.get(&id) {
             Some(path)
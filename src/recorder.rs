@@ -0,0 +1,336 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use egui::Ui;
+use log::{error, info, warn};
+use ringbuf::Consumer;
+
+use crate::debugger::{DebuggerOutput, ProbeKind};
+
+/// Recordings stop by themselves after this long so memory use stays bounded
+pub const MAX_RECORD_SECONDS: f32 = 600.0;
+/// Seconds of values the audio thread can get ahead of the recording thread
+const RECORD_BUFFER_SECONDS: f32 = 2.0;
+const RECORD_POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    /// 32 bit float WAV with a channel for each recorded debugger channel
+    Wav,
+    /// A column for each recorded debugger channel, with the names as header
+    Csv,
+}
+
+impl RecordFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            RecordFormat::Wav => "wav",
+            RecordFormat::Csv => "csv",
+        }
+    }
+}
+
+struct Column {
+    name: String,
+    consumer: Consumer<f32>,
+    values: Vec<f32>,
+}
+
+struct Recording {
+    columns: Vec<Column>,
+    sample_rate: f32,
+    max_len: usize,
+}
+
+impl Recording {
+    /// Move the values the audio thread sent so far into the columns. Returns true once the
+    /// recording is long enough.
+    fn drain(&mut self) -> bool {
+        for column in self.columns.iter_mut() {
+            let remaining = self.max_len.saturating_sub(column.values.len());
+            let values = &mut column.values;
+            column.consumer.pop_each(
+                |v| {
+                    values.push(v);
+                    true
+                },
+                Some(remaining),
+            );
+        }
+        self.columns
+            .iter()
+            .any(|column| column.values.len() >= self.max_len)
+    }
+}
+
+/// Records debugger channels on a background thread, which writes them to a file when done.
+///
+/// The audio thread copies the values of the recorded channels into buffers of their own, so
+/// nothing depends on the debug panel being drawn and recordings keep going while the code
+/// editor is closed.
+pub struct Recorder {
+    pub format: RecordFormat,
+    /// Seconds to record, 0.0 records until stopped
+    pub seconds: f32,
+    /// Indices of the debugger channels to record
+    pub selected: Vec<usize>,
+    /// Set while the recording thread runs, cleared to stop it
+    running: Arc<AtomicBool>,
+    /// Result of the last write, set by the recording thread
+    status: Arc<Mutex<String>>,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder {
+            format: RecordFormat::Wav,
+            seconds: 0.0,
+            selected: Vec::new(),
+            running: Arc::new(AtomicBool::new(false)),
+            status: Arc::new(Mutex::new(String::new())),
+        }
+    }
+}
+
+impl Recorder {
+    pub fn is_recording(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    /// Start recording the selected channels, the file is written to `dir/recordings` once the
+    /// recording is stopped or reaches its length.
+    pub fn start(&mut self, debug_out: &mut DebuggerOutput, dir: &Path, name: &str) {
+        if self.is_recording() {
+            return;
+        }
+        let sample_rate = debug_out.sample_rate();
+        let buffer_size = (RECORD_BUFFER_SECONDS * sample_rate) as usize;
+        let dropped = Arc::new(AtomicUsize::new(0));
+        let mut columns = Vec::new();
+        for index in self.selected.iter() {
+            let name = match debug_out.channels.get(*index) {
+                Some(Some(channel)) => channel.name.clone(),
+                _ => continue,
+            };
+            if let Some(consumer) = debug_out.record_channel(*index, buffer_size, dropped.clone()) {
+                columns.push(Column {
+                    name,
+                    consumer,
+                    values: Vec::new(),
+                });
+            }
+        }
+        if columns.is_empty() {
+            return;
+        }
+        let seconds = if self.seconds > 0.0 {
+            self.seconds.min(MAX_RECORD_SECONDS)
+        } else {
+            MAX_RECORD_SECONDS
+        };
+        let mut recording = Recording {
+            columns,
+            sample_rate,
+            max_len: (seconds * sample_rate) as usize,
+        };
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let file_name = format!("{}_{}", name, time);
+        let dir = dir.join("recordings");
+        let format = self.format;
+        let stop_recording = debug_out.stop_recording();
+        let running = self.running.clone();
+        let status = self.status.clone();
+        running.store(true, Ordering::Relaxed);
+        thread::spawn(move || {
+            while running.load(Ordering::Relaxed) && !recording.drain() {
+                thread::sleep(RECORD_POLL_INTERVAL);
+            }
+            stop_recording.store(true, Ordering::Relaxed);
+            recording.drain();
+            running.store(false, Ordering::Relaxed);
+            *status.lock().unwrap() = String::from("Writing...");
+            let dropped = dropped.load(Ordering::Relaxed);
+            let message = write_recording(&dir, &file_name, format, &recording, dropped);
+            *status.lock().unwrap() = message;
+        });
+    }
+
+    /// Stop recording, what was recorded so far is written in the background
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::Relaxed);
+    }
+
+    pub fn status(&self) -> String {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+/// Write the file and describe the result. Values the audio thread couldn't hand over leave gaps,
+/// those files get a `_gaps` suffix so they aren't mistaken for a continuous recording.
+fn write_recording(
+    dir: &Path,
+    file_name: &str,
+    format: RecordFormat,
+    recording: &Recording,
+    dropped: usize,
+) -> String {
+    let suffix = if dropped > 0 { "_gaps" } else { "" };
+    let path = dir.join(format!("{}{}.{}", file_name, suffix, format.extension()));
+    match write_file(&path, format, recording) {
+        Ok(()) if dropped > 0 => {
+            warn!(
+                "recording written to {:?}, {} values dropped",
+                path, dropped
+            );
+            format!(
+                "Saved {}, {} values were dropped so it has gaps",
+                path.display(),
+                dropped
+            )
+        }
+        Ok(()) => {
+            info!("recording written to {:?}", path);
+            format!("Saved {}", path.display())
+        }
+        Err(e) => {
+            error!("could not write recording {:?}: {}", path, e);
+            format!("Error: {}", e)
+        }
+    }
+}
+
+fn write_file(path: &Path, format: RecordFormat, recording: &Recording) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let columns: Vec<&[f32]> = recording
+        .columns
+        .iter()
+        .map(|column| &column.values[..column.values.len().min(recording.max_len)])
+        .collect();
+    match format {
+        RecordFormat::Wav => write_wav(path, recording.sample_rate as u32, &columns),
+        RecordFormat::Csv => {
+            let names: Vec<&str> = recording
+                .columns
+                .iter()
+                .map(|column| column.name.as_str())
+                .collect();
+            write_csv(BufWriter::new(File::create(path)?), &names, &columns)
+        }
+    }
+}
+
+/// Shorter columns, like per block channels, are padded with zeros
+fn write_wav(path: &Path, sample_rate: u32, columns: &[&[f32]]) -> anyhow::Result<()> {
+    let spec = hound::WavSpec {
+        channels: columns.len() as u16,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let mut writer = hound::WavWriter::create(path, spec)?;
+    let len = columns.iter().map(|c| c.len()).max().unwrap_or(0);
+    for i in 0..len {
+        for column in columns {
+            writer.write_sample(*column.get(i).unwrap_or(&0.0))?;
+        }
+    }
+    writer.finalize()?;
+    Ok(())
+}
+
+/// Shorter columns, like per block channels, leave their remaining cells empty
+fn write_csv(mut out: impl Write, names: &[&str], columns: &[&[f32]]) -> anyhow::Result<()> {
+    let header: Vec<String> = names
+        .iter()
+        .map(|name| format!("\"{}\"", name.replace("\"", "\"\"")))
+        .collect();
+    writeln!(out, "{}", header.join(","))?;
+    let len = columns.iter().map(|c| c.len()).max().unwrap_or(0);
+    for i in 0..len {
+        let row: Vec<String> = columns
+            .iter()
+            .map(|column| column.get(i).map(|v| v.to_string()).unwrap_or_default())
+            .collect();
+        writeln!(out, "{}", row.join(","))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// Channel selection and record controls. Returns true when a recording should be started.
+pub fn recorder_ui(ui: &mut Ui, recorder: &mut Recorder, debug_out: &DebuggerOutput) -> bool {
+    let mut start = false;
+    egui::CollapsingHeader::new("Record").show(ui, |ui| {
+        ui.horizontal_wrapped(|ui| {
+            for (index, channel) in debug_out.channels.iter().enumerate() {
+                if let Some(channel) = channel {
+                    if channel.kind == ProbeKind::Vector {
+                        continue;
+                    }
+                    let mut selected = recorder.selected.contains(&index);
+                    let response = ui.add_enabled(
+                        !recorder.is_recording(),
+                        egui::Checkbox::new(&mut selected, &channel.name),
+                    );
+                    if response.changed() {
+                        if selected {
+                            recorder.selected.push(index);
+                        } else {
+                            recorder.selected.retain(|i| *i != index);
+                        }
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut recorder.format, RecordFormat::Wav, "WAV");
+            ui.selectable_value(&mut recorder.format, RecordFormat::Csv, "CSV");
+            ui.label("Seconds");
+            ui.add(
+                egui::DragValue::new(&mut recorder.seconds)
+                    .speed(0.1)
+                    .clamp_range(0.0..=MAX_RECORD_SECONDS),
+            );
+        });
+        ui.horizontal(|ui| {
+            if recorder.is_recording() {
+                if ui.button("Stop").clicked() {
+                    recorder.stop();
+                }
+                ui.label("Recording");
+            } else {
+                start = ui.button("Record").clicked();
+                ui.label(recorder.status());
+            }
+        });
+    });
+    start
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_write_csv() {
+        let mut out = Vec::new();
+        write_csv(&mut out, &["env", "gr"], &[&[1.0, 0.5], &[-3.0]]).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\"env\",\"gr\"\n1,-3\n0.5,\n"
+        );
+    }
+}
@@ -1,11 +1,23 @@
 use std::ffi::CStr;
 
-use crate::units::ConsumerRingBuf;
+use crate::console::LogValue;
+use crate::curve_editor::{curve_editor_ui, Curve};
+use crate::debugger::{DebuggerInput, ProbeKind};
+use crate::freeze::TriggerReason;
+use crate::meters::{gain_reduction_meter_ui, level_meter_ui};
+use crate::shared_data::{
+    DSPSharedRef, SharedKind, UISharedRef, SHARED_CURVES, SHARED_METERS, SHARED_PITCH_TRACKERS,
+    SHARED_STREAMS, SHARED_VALUES,
+};
+use crate::shortcuts::KeyBinding;
+use crate::text_arena::TextArenaRef;
+use crate::theme;
+use crate::SarusUIModelParams;
 use egui::plot::Line;
 use egui::plot::Plot;
 use egui::plot::Value;
 use egui::plot::Values;
-use egui::Ui;
+use egui::{Response, Ui};
 use sarus::decl;
 use sarus::frontend::Arg;
 use sarus::frontend::Declaration;
@@ -34,16 +46,47 @@ pub struct SliceBool {
     pub len: i64,
 }
 
+// Must match the size of the `TextInput` struct declared in `compiler::compile`
+pub const TEXT_INPUT_CAPACITY: usize = 256;
+
+/// Fixed capacity, nul terminated text buffer stored in `EditorState`.
+#[repr(C)]
+pub struct TextInput {
+    pub bytes: [u8; TEXT_INPUT_CAPACITY],
+}
+
+impl TextInput {
+    pub fn text(&self) -> String {
+        let len = self
+            .bytes
+            .iter()
+            .position(|b| *b == 0)
+            .unwrap_or(TEXT_INPUT_CAPACITY);
+        String::from_utf8_lossy(&self.bytes[..len]).to_string()
+    }
+
+    /// Copy `s` into the buffer, truncating on a char boundary if it doesn't fit.
+    pub fn set(&mut self, s: &str) {
+        let mut len = s.len().min(TEXT_INPUT_CAPACITY - 1);
+        while !s.is_char_boundary(len) {
+            len -= 1;
+        }
+        self.bytes[..len].copy_from_slice(&s.as_bytes()[..len]);
+        self.bytes[len] = 0;
+    }
+}
+
 extern "C" fn plot(ui: &mut Ui, buf: SliceF32) {
     let buf_slice = unsafe { std::slice::from_raw_parts(buf.arr, buf.len as usize) };
+    plot_values(ui, "plot", buf_slice.iter());
+}
+
+fn plot_values<'a>(ui: &mut Ui, id: impl std::hash::Hash, values: impl Iterator<Item = &'a f32>) {
     let line = Line::new(Values::from_values_iter(
-        buf_slice
-            .iter()
-            .enumerate()
-            .map(|(i, v)| Value::new(i as f32, *v)),
+        values.enumerate().map(|(i, v)| Value::new(i as f32, *v)),
     ));
     ui.add(
-        Plot::new("plot")
+        Plot::new(id)
             .width(200.0)
             .line(line)
             .view_aspect(1.0)
@@ -72,6 +115,109 @@ extern "C" fn slider(ui: &mut Ui, s: *const i8, x: f32, range_btm: f32, range_to
     slider_f32
 }
 
+extern "C" fn drag_value(
+    ui: &mut Ui,
+    s: *const i8,
+    x: f32,
+    range_btm: f32,
+    range_top: f32,
+    speed: f32,
+    suffix: *const i8,
+) -> f32 {
+    let s = unsafe { CStr::from_ptr(s).to_str().unwrap() };
+    let suffix = unsafe { CStr::from_ptr(suffix).to_str().unwrap() };
+    let mut value = x;
+    ui.horizontal(|ui| {
+        ui.add(
+            egui::DragValue::new(&mut value)
+                .clamp_range(range_btm..=range_top)
+                .speed(speed)
+                .suffix(suffix),
+        );
+        ui.label(s);
+    });
+    value
+}
+
+extern "C" fn text_input(ui: &mut Ui, s: *const i8, input: &mut TextInput) -> bool {
+    let s = unsafe { CStr::from_ptr(s).to_str().unwrap() };
+    let mut text = input.text();
+    let changed = ui
+        .horizontal(|ui| {
+            let changed = ui.text_edit_singleline(&mut text).changed();
+            ui.label(s);
+            changed
+        })
+        .inner;
+    if changed {
+        input.set(&text);
+    }
+    changed
+}
+
+extern "C" fn text_input_clear(input: &mut TextInput) {
+    input.set("");
+}
+
+extern "C" fn text_input_as_str(input: &mut TextInput) -> *const i8 {
+    input.bytes[TEXT_INPUT_CAPACITY - 1] = 0;
+    input.bytes.as_ptr() as *const i8
+}
+
+extern "C" fn text_input_parse_f32(input: &mut TextInput, default: f32) -> f32 {
+    input.text().trim().parse().unwrap_or(default)
+}
+
+extern "C" fn set_dark_mode(ui: &mut Ui, dark_mode: bool) {
+    if ui.visuals().dark_mode != dark_mode {
+        let mut style: egui::Style = (*ui.ctx().style()).clone();
+        style.visuals = if dark_mode {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        };
+        ui.ctx().set_style(style);
+    }
+}
+
+extern "C" fn set_accent_color(ui: &mut Ui, r: i64, g: i64, b: i64) {
+    let color = egui::Color32::from_rgb(
+        r.clamp(0, 255) as u8,
+        g.clamp(0, 255) as u8,
+        b.clamp(0, 255) as u8,
+    );
+    let mut style: egui::Style = (*ui.ctx().style()).clone();
+    theme::set_accent_color(&mut style.visuals, color);
+    ui.ctx().set_style(style);
+}
+
+extern "C" fn set_font_size(ui: &mut Ui, size: f32) {
+    theme::set_font_size(ui.ctx(), size.clamp(6.0, 100.0));
+}
+
+extern "C" fn set_spacing(ui: &mut Ui, x: f32, y: f32) {
+    let mut style: egui::Style = (*ui.ctx().style()).clone();
+    style.spacing.item_spacing = egui::vec2(x, y);
+    ui.ctx().set_style(style);
+}
+
+fn key_binding(binding: *const i8) -> Option<KeyBinding> {
+    KeyBinding::parse(unsafe { CStr::from_ptr(binding).to_str().unwrap() })
+}
+
+/// True on the frame `binding` (like `Space` or `Ctrl+K`) was pressed
+extern "C" fn key_pressed(ui: &mut Ui, binding: *const i8) -> bool {
+    key_binding(binding).map_or(false, |binding| binding.pressed(&ui.input()))
+}
+
+extern "C" fn key_down(ui: &mut Ui, binding: *const i8) -> bool {
+    key_binding(binding).map_or(false, |binding| binding.down(&ui.input()))
+}
+
+extern "C" fn key_released(ui: &mut Ui, binding: *const i8) -> bool {
+    key_binding(binding).map_or(false, |binding| binding.released(&ui.input()))
+}
+
 extern "C" fn to_range(x: f32, bottom: f32, top: f32) -> f32 {
     x * (top - bottom) + bottom
 }
@@ -92,10 +238,39 @@ extern "C" fn db_to_lin(x: f32) -> f32 {
     (10.0f32).powf(x * 0.05)
 }
 
-extern "C" fn lin_to_db(x: f32) -> f32 {
+pub extern "C" fn lin_to_db(x: f32) -> f32 {
     x.max(0.0).log(10.0) * 20.0
 }
 
+/// How much slower the value changes when dragging with ctrl held
+const FINE_ADJUST: f32 = 0.1;
+
+/// Slider over a normalized value. Dragging with ctrl held adjusts the value more finely and
+/// double clicking resets it to `default`, if there is one.
+pub fn normalized_slider_ui(
+    ui: &mut Ui,
+    text: String,
+    x: f32,
+    default: Option<f32>,
+) -> (f32, Response) {
+    let mut value = x;
+    let response = ui.add(
+        egui::Slider::new(&mut value, 0f32..=1f32)
+            .text(text)
+            .show_value(false),
+    );
+    if response.dragged() && ui.input().modifiers.ctrl {
+        let delta = response.drag_delta().x / ui.spacing().slider_width;
+        value = (x + delta * FINE_ADJUST).clamp(0.0, 1.0);
+    }
+    if response.double_clicked() {
+        if let Some(default) = default {
+            value = default;
+        }
+    }
+    (value, response)
+}
+
 extern "C" fn slider_normalized(
     ui: &mut Ui,
     s: *const i8,
@@ -110,33 +285,332 @@ extern "C" fn slider_normalized(
         s,
         from_normalized(x, range_btm, range_top, exponent)
     );
-    let mut slider_f32 = x;
-    ui.add(
-        egui::Slider::new(&mut slider_f32, 0f32..=1f32)
-            .text(text)
-            .show_value(false),
-    );
-    slider_f32
+    normalized_slider_ui(ui, text, x, None).0
+}
+
+/// Slider bound to parameter `index` (0 is `p1`). Sends begin/end gestures to the host
+/// while it's being dragged and resets to `default` (in the given range) on double click.
+#[allow(clippy::too_many_arguments)]
+extern "C" fn param_slider(
+    ui: &mut Ui,
+    s: *const i8,
+    params: &mut SarusUIModelParams,
+    index: i64,
+    default: f32,
+    range_btm: f32,
+    range_top: f32,
+    exponent: f32,
+) {
+    let s = unsafe { CStr::from_ptr(s).to_str().unwrap() };
+    if index < 0 {
+        return;
+    }
+    let index = index as usize;
+    if let Some(x) = params.param_mut(index).map(|x| *x) {
+        let text = format!(
+            "{} {:.2}",
+            s,
+            from_normalized(x, range_btm, range_top, exponent)
+        );
+        let default = to_normalized(default, range_btm, range_top, exponent);
+        let (value, response) = normalized_slider_ui(ui, text, x, Some(default));
+        if response.drag_started() || response.double_clicked() {
+            params.begin_gesture(index);
+        }
+        if response.drag_released() || response.double_clicked() {
+            params.end_gesture(index);
+        }
+        if let Some(x) = params.param_mut(index) {
+            *x = value;
+        }
+    }
 }
 
-pub struct DebuggerInput {
-    pub producers: Vec<ringbuf::Producer<f32>>,
+extern "C" fn format_number(text: &mut TextArenaRef, x: f32, precision: i64) -> *const i8 {
+    let precision = precision.clamp(0, 10) as usize;
+    text.get().alloc(format!("{:.*}", precision, x))
 }
 
-pub struct DebuggerOutput {
-    pub consumers: Vec<ConsumerRingBuf<f32>>,
+extern "C" fn format_number_unit(
+    text: &mut TextArenaRef,
+    x: f32,
+    precision: i64,
+    unit: *const i8,
+) -> *const i8 {
+    let unit = unsafe { CStr::from_ptr(unit).to_str().unwrap() };
+    let precision = precision.clamp(0, 10) as usize;
+    text.get().alloc(format!("{:.*} {}", precision, x, unit))
 }
 
-extern "C" fn show(debugger: &mut DebuggerInput, i: i64, v: f32) -> bool {
-    if i >= 0 {
-        let i = i as usize;
-        if i < debugger.producers.len() && !debugger.producers[i].is_full() {
-            if let Ok(_) = debugger.producers[i as usize].push(v) {
-                return true;
-            }
+extern "C" fn format_integer(text: &mut TextArenaRef, x: i64) -> *const i8 {
+    text.get().alloc(format!("{}", x))
+}
+
+extern "C" fn concat(text: &mut TextArenaRef, a: *const i8, b: *const i8) -> *const i8 {
+    let a = unsafe { CStr::from_ptr(a).to_str().unwrap() };
+    let b = unsafe { CStr::from_ptr(b).to_str().unwrap() };
+    text.get().alloc(format!("{}{}", a, b))
+}
+
+extern "C" fn level_meter(ui: &mut Ui, s: *const i8, peak: f32, rms: f32) {
+    let s = unsafe { CStr::from_ptr(s).to_str().unwrap() };
+    level_meter_ui(ui, s, peak, rms);
+}
+
+extern "C" fn gain_reduction_meter(ui: &mut Ui, s: *const i8, gain_reduction_db: f32) {
+    let s = unsafe { CStr::from_ptr(s).to_str().unwrap() };
+    gain_reduction_meter_ui(ui, s, gain_reduction_db);
+}
+
+extern "C" fn curve_editor(ui: &mut Ui, curve: &mut Curve) -> bool {
+    curve_editor_ui(ui, curve).changed()
+}
+
+extern "C" fn curve_reset(curve: &mut Curve) {
+    *curve = Curve::default();
+}
+
+extern "C" fn curve_eval(curve: &Curve, x: f32) -> f32 {
+    curve.eval(x)
+}
+
+extern "C" fn set_curve(shared: &mut UISharedRef, i: i64, curve: &Curve) {
+    if i >= 0 && (i as usize) < SHARED_CURVES {
+        shared.get().curves_in[i as usize].write(*curve);
+    }
+}
+
+extern "C" fn eval_curve(shared: &mut DSPSharedRef, i: i64, x: f32) -> f32 {
+    if i >= 0 && (i as usize) < SHARED_CURVES {
+        shared.get().curves_out[i as usize].read().eval(x)
+    } else {
+        0.0
+    }
+}
+
+extern "C" fn set_value(shared: &mut DSPSharedRef, i: i64, v: f32) {
+    if i >= 0 && (i as usize) < SHARED_VALUES {
+        shared.get().values[i as usize] = v;
+    }
+}
+
+extern "C" fn push_value(shared: &mut DSPSharedRef, i: i64, v: f32) -> bool {
+    if i >= 0 && (i as usize) < SHARED_STREAMS {
+        let stream = &mut shared.get().streams[i as usize];
+        if !stream.is_full() && stream.push(v).is_ok() {
+            return true;
         }
     }
-    return false;
+    false
+}
+
+/// Add a sample to pitch tracker i, see `PitchTracker::push` for what it costs
+extern "C" fn pitch_push(shared: &mut DSPSharedRef, i: i64, v: f32) {
+    if i >= 0 && (i as usize) < SHARED_PITCH_TRACKERS {
+        shared.get().pitch[i as usize].push(v);
+    }
+}
+
+/// Frequency found by pitch tracker i, 0.0 if there is none
+extern "C" fn pitch(shared: &mut DSPSharedRef, i: i64, sample_rate: f32) -> f32 {
+    if i >= 0 && (i as usize) < SHARED_PITCH_TRACKERS {
+        shared.get().pitch[i as usize].frequency(sample_rate)
+    } else {
+        0.0
+    }
+}
+
+extern "C" fn get_value(shared: &mut UISharedRef, i: i64) -> f32 {
+    if i >= 0 && (i as usize) < SHARED_VALUES {
+        shared.get().values[i as usize]
+    } else {
+        0.0
+    }
+}
+
+extern "C" fn plot_stream(ui: &mut Ui, shared: &mut UISharedRef, i: i64) {
+    if i >= 0 && (i as usize) < SHARED_STREAMS {
+        plot_values(
+            ui,
+            format!("stream{}", i),
+            shared.get().streams[i as usize].iter(),
+        );
+    }
+}
+
+fn index_or_negative(index: Option<usize>) -> i64 {
+    match index {
+        Some(index) => index as i64,
+        None => -1,
+    }
+}
+
+/// Index of the shared value called `name`, declared by the process function.
+/// Returns -1 if there are no free values.
+extern "C" fn declare_value(shared: &mut DSPSharedRef, name: *const i8) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    index_or_negative(shared.get().declare(SharedKind::Value, name))
+}
+
+extern "C" fn declare_stream(shared: &mut DSPSharedRef, name: *const i8) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    index_or_negative(shared.get().declare(SharedKind::Stream, name))
+}
+
+/// Index of the shared curve called `name`, declared by the editor function
+extern "C" fn declare_curve(shared: &mut UISharedRef, name: *const i8) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    index_or_negative(shared.get().declare_curve(name))
+}
+
+/// Index of the shared meter called `name`, declared by the process function
+extern "C" fn declare_meter(shared: &mut DSPSharedRef, name: *const i8) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    index_or_negative(shared.get().declare(SharedKind::Meter, name))
+}
+
+extern "C" fn meter_push(shared: &mut DSPSharedRef, i: i64, v: f32) {
+    if i >= 0 {
+        shared.get().meter_push(i as usize, v);
+    }
+}
+
+extern "C" fn find_meter(shared: &mut UISharedRef, name: *const i8) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    index_or_negative(shared.get().find(SharedKind::Meter, name))
+}
+
+/// Highest absolute value meter `i` received since the previous frame
+extern "C" fn meter_peak(shared: &mut UISharedRef, i: i64) -> f32 {
+    if i >= 0 && (i as usize) < SHARED_METERS {
+        shared.get().meters[i as usize].peak
+    } else {
+        0.0
+    }
+}
+
+/// RMS of the samples meter `i` received since the previous frame
+extern "C" fn meter_rms(shared: &mut UISharedRef, i: i64) -> f32 {
+    if i >= 0 && (i as usize) < SHARED_METERS {
+        shared.get().meters[i as usize].rms
+    } else {
+        0.0
+    }
+}
+
+/// Index of a value declared by the process function, -1 until it has been declared
+extern "C" fn find_value(shared: &mut UISharedRef, name: *const i8) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    index_or_negative(shared.get().find(SharedKind::Value, name))
+}
+
+extern "C" fn find_stream(shared: &mut UISharedRef, name: *const i8) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    index_or_negative(shared.get().find(SharedKind::Stream, name))
+}
+
+/// Index of a curve declared by the editor function, -1 until it has been declared
+extern "C" fn find_curve(shared: &mut DSPSharedRef, name: *const i8) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    index_or_negative(shared.get().find_curve(name))
+}
+
+extern "C" fn show(debugger: &mut DebuggerInput, i: i64, v: f32) -> bool {
+    i >= 0 && debugger.push(i as usize, v)
+}
+
+extern "C" fn show_int(debugger: &mut DebuggerInput, i: i64, v: i64) -> bool {
+    i >= 0 && debugger.push_int(i as usize, v)
+}
+
+extern "C" fn show_bool(debugger: &mut DebuggerInput, i: i64, v: bool) -> bool {
+    i >= 0 && debugger.push(i as usize, if v { 1.0 } else { 0.0 })
+}
+
+extern "C" fn show_vector(debugger: &mut DebuggerInput, i: i64, buf: SliceF32) -> bool {
+    let buf_slice = unsafe { std::slice::from_raw_parts(buf.arr, buf.len as usize) };
+    i >= 0 && debugger.push_vector(i as usize, buf_slice)
+}
+
+/// Freezes the debugger channels when `cond` is true. Returns `cond`.
+extern "C" fn trigger(debugger: &mut DebuggerInput, cond: bool) -> bool {
+    if cond {
+        debugger.trigger(TriggerReason::Script);
+    }
+    cond
+}
+
+fn print_value(debugger: &mut DebuggerInput, s: *const i8, value: LogValue) -> bool {
+    let s = unsafe { CStr::from_ptr(s).to_str().unwrap() };
+    debugger.print(s, value)
+}
+
+extern "C" fn print(debugger: &mut DebuggerInput, s: *const i8) -> bool {
+    print_value(debugger, s, LogValue::None)
+}
+
+extern "C" fn print_f32(debugger: &mut DebuggerInput, s: *const i8, v: f32) -> bool {
+    print_value(debugger, s, LogValue::Float(v))
+}
+
+extern "C" fn print_int(debugger: &mut DebuggerInput, s: *const i8, v: i64) -> bool {
+    print_value(debugger, s, LogValue::Int(v))
+}
+
+extern "C" fn print_bool(debugger: &mut DebuggerInput, s: *const i8, v: bool) -> bool {
+    print_value(debugger, s, LogValue::Bool(v))
+}
+
+fn ui_print_value(shared: &mut UISharedRef, s: *const i8, value: LogValue) -> bool {
+    let s = unsafe { CStr::from_ptr(s).to_str().unwrap() };
+    shared.get().print(s, value)
+}
+
+extern "C" fn ui_print(shared: &mut UISharedRef, s: *const i8) -> bool {
+    ui_print_value(shared, s, LogValue::None)
+}
+
+extern "C" fn ui_print_f32(shared: &mut UISharedRef, s: *const i8, v: f32) -> bool {
+    ui_print_value(shared, s, LogValue::Float(v))
+}
+
+extern "C" fn ui_print_int(shared: &mut UISharedRef, s: *const i8, v: i64) -> bool {
+    ui_print_value(shared, s, LogValue::Int(v))
+}
+
+extern "C" fn ui_print_bool(shared: &mut UISharedRef, s: *const i8, v: bool) -> bool {
+    ui_print_value(shared, s, LogValue::Bool(v))
+}
+
+/// Index of the debugger channel called `name`, which is created with a buffer of `size`
+/// values if it doesn't exist yet. Returns -1 if there are no free channels.
+fn probe_kind(debugger: &mut DebuggerInput, name: *const i8, kind: ProbeKind, size: i64) -> i64 {
+    let name = unsafe { CStr::from_ptr(name).to_str().unwrap() };
+    match debugger.probe(name, kind, size.max(0) as usize) {
+        Some(index) => index as i64,
+        None => -1,
+    }
+}
+
+extern "C" fn probe(debugger: &mut DebuggerInput, name: *const i8, size: i64) -> i64 {
+    probe_kind(debugger, name, ProbeKind::Samples, size)
+}
+
+extern "C" fn probe_block(debugger: &mut DebuggerInput, name: *const i8, size: i64) -> i64 {
+    probe_kind(debugger, name, ProbeKind::Block, size)
+}
+
+extern "C" fn probe_int(debugger: &mut DebuggerInput, name: *const i8, size: i64) -> i64 {
+    probe_kind(debugger, name, ProbeKind::Integer, size)
+}
+
+extern "C" fn probe_bool(debugger: &mut DebuggerInput, name: *const i8, size: i64) -> i64 {
+    probe_kind(debugger, name, ProbeKind::Bool, size)
+}
+
+/// `len` is the length of the arrays sent with `show_vector`
+extern "C" fn probe_vector(debugger: &mut DebuggerInput, name: *const i8, len: i64) -> i64 {
+    probe_kind(debugger, name, ProbeKind::Vector, len)
 }
 
 #[rustfmt::skip]
@@ -150,6 +624,54 @@ pub fn append_egui(
     decl!(prog, jb, "Ui.button",button,(struct_t("Ui"),address_t()),(bool_t()));
     decl!(prog, jb, "Ui.slider",slider,(struct_t("Ui"),address_t(),f32_t(),f32_t(),f32_t()),(f32_t()));
     decl!(prog, jb, "Ui.slider_normalized",slider_normalized,(struct_t("Ui"),address_t(),f32_t(),f32_t(),f32_t(),f32_t()),(f32_t()));
+    decl!(prog, jb, "Ui.param_slider",param_slider,(struct_t("Ui"),address_t(),struct_t("SarusUIModelParams"),i64_t(),f32_t(),f32_t(),f32_t(),f32_t()),());
+    decl!(prog, jb, "Ui.drag_value",drag_value,(struct_t("Ui"),address_t(),f32_t(),f32_t(),f32_t(),f32_t(),address_t()),(f32_t()));
+    decl!(prog, jb, "Ui.text_input",text_input,(struct_t("Ui"),address_t(),struct_t("TextInput")),(bool_t()));
+    decl!(prog, jb, "Ui.set_dark_mode",set_dark_mode,(struct_t("Ui"),bool_t()),());
+    decl!(prog, jb, "Ui.set_accent_color",set_accent_color,(struct_t("Ui"),i64_t(),i64_t(),i64_t()),());
+    decl!(prog, jb, "Ui.set_font_size",set_font_size,(struct_t("Ui"),f32_t()),());
+    decl!(prog, jb, "Ui.set_spacing",set_spacing,(struct_t("Ui"),f32_t(),f32_t()),());
+    decl!(prog, jb, "Ui.key_pressed",key_pressed,(struct_t("Ui"),address_t()),(bool_t()));
+    decl!(prog, jb, "Ui.key_down",key_down,(struct_t("Ui"),address_t()),(bool_t()));
+    decl!(prog, jb, "Ui.key_released",key_released,(struct_t("Ui"),address_t()),(bool_t()));
+    decl!(prog, jb, "Ui.curve_editor",curve_editor,(struct_t("Ui"),struct_t("Curve")),(bool_t()));
+    decl!(prog, jb, "Ui.plot_stream",plot_stream,(struct_t("Ui"),struct_t("UIShared"),i64_t()),());
+    decl!(prog, jb, "Ui.level_meter",level_meter,(struct_t("Ui"),address_t(),f32_t(),f32_t()),());
+    decl!(prog, jb, "Ui.gain_reduction_meter",gain_reduction_meter,(struct_t("Ui"),address_t(),f32_t()),());
+
+    decl!(prog, jb, "TextInput.clear",     text_input_clear, (struct_t("TextInput")),           ());
+    decl!(prog, jb, "TextInput.as_str",    text_input_as_str,(struct_t("TextInput")),           (address_t()));
+    decl!(prog, jb, "TextInput.parse_f32", text_input_parse_f32,(struct_t("TextInput"),f32_t()),(f32_t()));
+
+    decl!(prog, jb, "Curve.reset",         curve_reset,      (struct_t("Curve")),               ());
+    decl!(prog, jb, "Curve.eval",          curve_eval,       (struct_t("Curve"),f32_t()),       (f32_t()));
+    decl!(prog, jb, "UIShared.set_curve",  set_curve,        (struct_t("UIShared"),i64_t(),struct_t("Curve")), ());
+    decl!(prog, jb, "UIShared.print",      ui_print,         (struct_t("UIShared"),address_t()), (bool_t()));
+    decl!(prog, jb, "UIShared.print_f32",  ui_print_f32,     (struct_t("UIShared"),address_t(),f32_t()), (bool_t()));
+    decl!(prog, jb, "UIShared.print_int",  ui_print_int,     (struct_t("UIShared"),address_t(),i64_t()), (bool_t()));
+    decl!(prog, jb, "UIShared.print_bool", ui_print_bool,    (struct_t("UIShared"),address_t(),bool_t()), (bool_t()));
+    decl!(prog, jb, "DSPShared.eval_curve",eval_curve,       (struct_t("DSPShared"),i64_t(),f32_t()), (f32_t()));
+    decl!(prog, jb, "DSPShared.set",       set_value,        (struct_t("DSPShared"),i64_t(),f32_t()), ());
+    decl!(prog, jb, "DSPShared.push",      push_value,       (struct_t("DSPShared"),i64_t(),f32_t()), (bool_t()));
+    decl!(prog, jb, "DSPShared.pitch_push",pitch_push,       (struct_t("DSPShared"),i64_t(),f32_t()), ());
+    decl!(prog, jb, "DSPShared.pitch",     pitch,            (struct_t("DSPShared"),i64_t(),f32_t()), (f32_t()));
+    decl!(prog, jb, "UIShared.get",        get_value,        (struct_t("UIShared"),i64_t()),  (f32_t()));
+    decl!(prog, jb, "UIShared.curve",      declare_curve,    (struct_t("UIShared"),address_t()), (i64_t()));
+    decl!(prog, jb, "UIShared.find_value", find_value,       (struct_t("UIShared"),address_t()), (i64_t()));
+    decl!(prog, jb, "UIShared.find_stream",find_stream,      (struct_t("UIShared"),address_t()), (i64_t()));
+    decl!(prog, jb, "DSPShared.value",     declare_value,    (struct_t("DSPShared"),address_t()), (i64_t()));
+    decl!(prog, jb, "DSPShared.stream",    declare_stream,   (struct_t("DSPShared"),address_t()), (i64_t()));
+    decl!(prog, jb, "DSPShared.find_curve",find_curve,       (struct_t("DSPShared"),address_t()), (i64_t()));
+    decl!(prog, jb, "DSPShared.meter",     declare_meter,    (struct_t("DSPShared"),address_t()), (i64_t()));
+    decl!(prog, jb, "DSPShared.meter_push",meter_push,       (struct_t("DSPShared"),i64_t(),f32_t()), ());
+    decl!(prog, jb, "UIShared.find_meter", find_meter,       (struct_t("UIShared"),address_t()), (i64_t()));
+    decl!(prog, jb, "UIShared.meter_peak", meter_peak,       (struct_t("UIShared"),i64_t()), (f32_t()));
+    decl!(prog, jb, "UIShared.meter_rms",  meter_rms,        (struct_t("UIShared"),i64_t()), (f32_t()));
+
+    decl!(prog, jb, "Text.number",         format_number,    (struct_t("Text"),f32_t(),i64_t()),             (address_t()));
+    decl!(prog, jb, "Text.number_unit",    format_number_unit,(struct_t("Text"),f32_t(),i64_t(),address_t()),(address_t()));
+    decl!(prog, jb, "Text.integer",        format_integer,   (struct_t("Text"),i64_t()),                     (address_t()));
+    decl!(prog, jb, "Text.concat",         concat,           (struct_t("Text"),address_t(),address_t()),     (address_t()));
     
     decl!(prog, jb, "f32.from_range",      from_range,       (f32_t(),f32_t(),f32_t()),         (f32_t()));
     decl!(prog, jb, "f32.to_range",        to_range,         (f32_t(),f32_t(),f32_t()),         (f32_t()));
@@ -159,6 +681,19 @@ pub fn append_egui(
     decl!(prog, jb, "f32.lin_to_db",       lin_to_db,        (f32_t()),                         (f32_t()));
 
     decl!(prog, jb, "Debugger.show",show,(struct_t("Debugger"),i64_t(),f32_t()),(bool_t()));
+    decl!(prog, jb, "Debugger.show_int",show_int,(struct_t("Debugger"),i64_t(),i64_t()),(bool_t()));
+    decl!(prog, jb, "Debugger.show_bool",show_bool,(struct_t("Debugger"),i64_t(),bool_t()),(bool_t()));
+    decl!(prog, jb, "Debugger.show_vector",show_vector,(struct_t("Debugger"),i64_t(),struct_t("Slice::f32")),(bool_t()));
+    decl!(prog, jb, "Debugger.trigger",trigger,(struct_t("Debugger"),bool_t()),(bool_t()));
+    decl!(prog, jb, "Debugger.print",print,(struct_t("Debugger"),address_t()),(bool_t()));
+    decl!(prog, jb, "Debugger.print_f32",print_f32,(struct_t("Debugger"),address_t(),f32_t()),(bool_t()));
+    decl!(prog, jb, "Debugger.print_int",print_int,(struct_t("Debugger"),address_t(),i64_t()),(bool_t()));
+    decl!(prog, jb, "Debugger.print_bool",print_bool,(struct_t("Debugger"),address_t(),bool_t()),(bool_t()));
+    decl!(prog, jb, "Debugger.probe",probe,(struct_t("Debugger"),address_t(),i64_t()),(i64_t()));
+    decl!(prog, jb, "Debugger.probe_block",probe_block,(struct_t("Debugger"),address_t(),i64_t()),(i64_t()));
+    decl!(prog, jb, "Debugger.probe_int",probe_int,(struct_t("Debugger"),address_t(),i64_t()),(i64_t()));
+    decl!(prog, jb, "Debugger.probe_bool",probe_bool,(struct_t("Debugger"),address_t(),i64_t()),(i64_t()));
+    decl!(prog, jb, "Debugger.probe_vector",probe_vector,(struct_t("Debugger"),address_t(),i64_t()),(i64_t()));
     prog.push(Declaration::StructMacro("Slice".to_string(), Box::new(f32_t())));
     prog.push(Declaration::StructMacro("Slice".to_string(), Box::new(i64_t())));
     prog.push(Declaration::StructMacro("Slice".to_string(), Box::new(bool_t())));
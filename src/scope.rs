@@ -0,0 +1,203 @@
+use serde::{Deserialize, Serialize};
+
+use egui::{
+    plot::{HLine, Line, Plot, Value, Values},
+    Color32, Stroke, Ui,
+};
+
+pub const MIN_TIME_BASE: usize = 16;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum TriggerMode {
+    /// Always show the latest samples
+    FreeRun,
+    Rising,
+    Falling,
+}
+
+/// Triggered oscilloscope over the samples of a debugger channel.
+pub struct Scope {
+    pub mode: TriggerMode,
+    pub level: f32,
+    /// Stop at the first trigger after being armed
+    pub single_shot: bool,
+    /// Keep showing the current capture
+    pub hold: bool,
+    /// Number of samples shown
+    pub time_base: usize,
+    /// The plot shows at least -scale..scale, 0.0 fits the plot to the capture
+    pub vertical_scale: f32,
+    pub triggered: bool,
+    captured: Vec<f32>,
+}
+
+impl Default for Scope {
+    fn default() -> Self {
+        Scope {
+            mode: TriggerMode::Rising,
+            level: 0.0,
+            single_shot: false,
+            hold: false,
+            time_base: 512,
+            vertical_scale: 1.0,
+            triggered: false,
+            captured: Vec::new(),
+        }
+    }
+}
+
+impl Scope {
+    fn crossed(&self, previous: f32, current: f32) -> bool {
+        match self.mode {
+            TriggerMode::FreeRun => true,
+            TriggerMode::Rising => previous < self.level && current >= self.level,
+            TriggerMode::Falling => previous > self.level && current <= self.level,
+        }
+    }
+
+    /// Look for a trigger in `data`, oldest sample first, of which the last `new_values` arrived
+    /// since the previous update. A quarter of the capture is taken from before the trigger.
+    pub fn update(&mut self, data: &[f32], new_values: usize) {
+        if self.hold || data.len() < MIN_TIME_BASE {
+            return;
+        }
+        let time_base = self.time_base.clamp(MIN_TIME_BASE, data.len());
+        if self.mode == TriggerMode::FreeRun {
+            self.captured.clear();
+            self.captured
+                .extend_from_slice(&data[data.len() - time_base..]);
+            self.triggered = true;
+            return;
+        }
+        let pre = time_base / 4;
+        // Latest trigger position that has a full capture after it
+        let last = data.len() - (time_base - pre);
+        // In single shot mode only triggers that weren't complete on the previous update count,
+        // so a trigger from before the scope was armed is not picked up
+        let first = if self.single_shot {
+            (last + 1).saturating_sub(new_values)
+        } else {
+            0
+        }
+        .max(pre)
+        .max(1);
+        let trigger = (first..=last)
+            .rev()
+            .find(|t| self.crossed(data[t - 1], data[*t]));
+        self.triggered = trigger.is_some();
+        if let Some(t) = trigger {
+            self.captured.clear();
+            self.captured
+                .extend_from_slice(&data[t - pre..t - pre + time_base]);
+            if self.single_shot {
+                self.hold = true;
+            }
+        }
+    }
+
+    pub fn captured(&self) -> &[f32] {
+        &self.captured
+    }
+}
+
+pub fn scope_ui(ui: &mut Ui, id: impl std::hash::Hash, scope: &mut Scope, max_time_base: usize) {
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut scope.mode, TriggerMode::FreeRun, "Free Run");
+        ui.selectable_value(&mut scope.mode, TriggerMode::Rising, "Rising");
+        ui.selectable_value(&mut scope.mode, TriggerMode::Falling, "Falling");
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut scope.single_shot, "Single Shot");
+        if scope.single_shot && ui.button("Arm").clicked() {
+            scope.hold = false;
+        }
+        ui.checkbox(&mut scope.hold, "Hold");
+        if scope.mode != TriggerMode::FreeRun {
+            ui.label(if scope.hold {
+                "Holding"
+            } else if scope.triggered {
+                "Triggered"
+            } else {
+                "Waiting"
+            });
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Level");
+        ui.add(egui::DragValue::new(&mut scope.level).speed(0.01));
+        ui.label("Scale");
+        ui.add(
+            egui::DragValue::new(&mut scope.vertical_scale)
+                .speed(0.01)
+                .clamp_range(0.0..=f32::MAX),
+        );
+    });
+    ui.add(
+        egui::Slider::new(
+            &mut scope.time_base,
+            MIN_TIME_BASE..=max_time_base.max(MIN_TIME_BASE),
+        )
+        .logarithmic(true)
+        .text("Samples"),
+    );
+
+    let line = Line::new(Values::from_values_iter(
+        scope
+            .captured()
+            .iter()
+            .enumerate()
+            .map(|(i, v)| Value::new(i as f32, *v)),
+    ));
+    let mut plot = Plot::new(id)
+        .line(line)
+        .view_aspect(1.0)
+        .allow_drag(false)
+        .allow_zoom(false)
+        .show_x(false)
+        .show_axes([false, true]);
+    if scope.vertical_scale > 0.0 {
+        plot = plot
+            .include_y(-scope.vertical_scale)
+            .include_y(scope.vertical_scale);
+    }
+    if scope.mode != TriggerMode::FreeRun {
+        plot = plot.hline(
+            HLine::new(scope.level).stroke(Stroke::new(1.0, Color32::from_rgb(230, 140, 40))),
+        );
+    }
+    ui.add(plot);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_scope_trigger() {
+        let mut data = vec![-1.0; 256];
+        data[100] = 1.0;
+        data[101] = 1.0;
+        let mut scope = Scope {
+            time_base: 64,
+            ..Default::default()
+        };
+        scope.update(&data, 256);
+        assert!(scope.triggered);
+        // The rising edge lands a quarter into the capture
+        assert_eq!(scope.captured()[15], -1.0);
+        assert_eq!(scope.captured()[16], 1.0);
+
+        scope.mode = TriggerMode::Falling;
+        scope.single_shot = true;
+        // The falling edge at 102 completed before it was armed
+        scope.update(&data, 0);
+        assert!(!scope.triggered && !scope.hold);
+        // 100 new samples with a falling edge at 152
+        let mut data = vec![-1.0; 256];
+        data[150] = 1.0;
+        data[151] = 1.0;
+        scope.update(&data, 100);
+        assert!(scope.triggered && scope.hold);
+        assert_eq!(scope.captured()[15], 1.0);
+        assert_eq!(scope.captured()[16], -1.0);
+    }
+}
@@ -0,0 +1,455 @@
+use ringbuf::{Consumer, Producer, RingBuffer};
+use triple_buffer::{Input, Output, TripleBuffer};
+
+use crate::{
+    console::{ConsoleInput, LogValue},
+    curve_editor::Curve,
+    debugger::ChannelName,
+    pitch::PitchTracker,
+    units::ConsumerRingBuf,
+};
+
+pub const SHARED_CURVES: usize = 8;
+pub const SHARED_VALUES: usize = 64;
+pub const SHARED_STREAMS: usize = 8;
+pub const SHARED_STREAM_SIZE: usize = 1024;
+pub const SHARED_PITCH_TRACKERS: usize = 4;
+pub const SHARED_METERS: usize = 16;
+// Number of declarations that can be in flight between the two sides
+const DECLARATION_QUEUE_SIZE: usize = 64;
+// Number of meter blocks that can be in flight, enough for many blocks per editor frame
+const METER_QUEUE_SIZE: usize = SHARED_METERS * 64;
+
+/// What a named shared channel carries. Values and streams are written by the process function
+/// and curves by the editor function.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SharedKind {
+    Value,
+    Stream,
+    Curve,
+    Meter,
+}
+
+/// Samples a meter received during a block. Sent whole to the editor after each block, which
+/// merges all the blocks it got since the previous frame, so no peak is missed however the
+/// blocks and frames line up.
+#[derive(Clone, Copy, Default)]
+struct MeterBlock {
+    peak: f32,
+    sum_squares: f32,
+    count: usize,
+}
+
+impl MeterBlock {
+    fn merge(&mut self, other: &MeterBlock) {
+        self.peak = self.peak.max(other.peak);
+        self.sum_squares += other.sum_squares;
+        self.count += other.count;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MeterReading {
+    pub peak: f32,
+    pub rms: f32,
+}
+
+/// Sent to the reading side when the writing side declares a channel
+struct Declaration {
+    kind: SharedKind,
+    name: ChannelName,
+    index: usize,
+    /// Compile the declaring function came from, see `UISharedData::set_generation`
+    generation: usize,
+}
+
+/// Slot of `name` in `names`, taking a free one and announcing it on `queue` if the name is new.
+/// Doesn't allocate.
+fn declare_name(
+    names: &mut [Option<ChannelName>],
+    queue: &mut Producer<Declaration>,
+    kind: SharedKind,
+    name: &str,
+    generation: usize,
+) -> Option<usize> {
+    let name = ChannelName::new(name);
+    if let Some(index) = names.iter().position(|n| *n == Some(name)) {
+        return Some(index);
+    }
+    let index = names.iter().position(Option::is_none)?;
+    queue
+        .push(Declaration {
+            kind,
+            name,
+            index,
+            generation,
+        })
+        .ok()?;
+    names[index] = Some(name);
+    Some(index)
+}
+
+/// Editor side of the data shared between the editor and process JITs.
+pub struct UISharedData {
+    pub curves_in: Vec<Input<Curve>>,
+    pub values: [f32; SHARED_VALUES],
+    values_out: Output<[f32; SHARED_VALUES]>,
+    pub streams: Vec<ConsumerRingBuf<f32>>,
+    /// Peak and RMS of each meter over the samples received since the previous frame
+    pub meters: [MeterReading; SHARED_METERS],
+    meter_blocks: Consumer<(usize, MeterBlock)>,
+    console: ConsoleInput,
+    generation: usize,
+    // Values and streams declared by the process function, with the generation they're from
+    names: Vec<(SharedKind, String, usize, usize)>,
+    declarations: Consumer<Declaration>,
+    curve_names: [Option<ChannelName>; SHARED_CURVES],
+    curve_declarations: Producer<Declaration>,
+}
+
+/// Process side of the data shared between the editor and process JITs.
+pub struct DSPSharedData {
+    pub curves_out: Vec<Output<Curve>>,
+    pub values: [f32; SHARED_VALUES],
+    values_in: Input<[f32; SHARED_VALUES]>,
+    pub streams: Vec<Producer<f32>>,
+    pub pitch: Vec<PitchTracker>,
+    meter_blocks: [MeterBlock; SHARED_METERS],
+    meter_queue: Producer<(usize, MeterBlock)>,
+    generation: usize,
+    value_names: [Option<ChannelName>; SHARED_VALUES],
+    stream_names: [Option<ChannelName>; SHARED_STREAMS],
+    meter_names: [Option<ChannelName>; SHARED_METERS],
+    declarations: Producer<Declaration>,
+    // Curves declared by the editor function, with the generation they're from
+    curve_names: [Option<(ChannelName, usize)>; SHARED_CURVES],
+    curve_declarations: Consumer<Declaration>,
+}
+
+/// `console` is where the editor function prints to
+pub fn new_shared_data(console: ConsoleInput) -> (UISharedData, DSPSharedData) {
+    let mut curves_in = Vec::new();
+    let mut curves_out = Vec::new();
+    for _ in 0..SHARED_CURVES {
+        let (input, output) = TripleBuffer::new(Curve::default()).split();
+        curves_in.push(input);
+        curves_out.push(output);
+    }
+    let (values_in, values_out) = TripleBuffer::new([0.0; SHARED_VALUES]).split();
+    let mut producers = Vec::new();
+    let mut consumers = Vec::new();
+    for _ in 0..SHARED_STREAMS {
+        let (prod, cons) = RingBuffer::<f32>::new(SHARED_STREAM_SIZE).split();
+        producers.push(prod);
+        consumers.push(ConsumerRingBuf::new(cons, SHARED_STREAM_SIZE));
+    }
+    let (meter_queue, meter_blocks) = RingBuffer::new(METER_QUEUE_SIZE).split();
+    let (declarations_in, declarations_out) = RingBuffer::new(DECLARATION_QUEUE_SIZE).split();
+    let (curve_declarations_in, curve_declarations_out) =
+        RingBuffer::new(DECLARATION_QUEUE_SIZE).split();
+    (
+        UISharedData {
+            curves_in,
+            values: [0.0; SHARED_VALUES],
+            values_out,
+            streams: consumers,
+            meters: [MeterReading::default(); SHARED_METERS],
+            meter_blocks,
+            console,
+            generation: 0,
+            names: Vec::new(),
+            declarations: declarations_out,
+            curve_names: [None; SHARED_CURVES],
+            curve_declarations: curve_declarations_in,
+        },
+        DSPSharedData {
+            curves_out,
+            values: [0.0; SHARED_VALUES],
+            values_in,
+            streams: producers,
+            pitch: (0..SHARED_PITCH_TRACKERS)
+                .map(|_| PitchTracker::new())
+                .collect(),
+            meter_blocks: [MeterBlock::default(); SHARED_METERS],
+            meter_queue,
+            generation: 0,
+            value_names: [None; SHARED_VALUES],
+            stream_names: [None; SHARED_STREAMS],
+            meter_names: [None; SHARED_METERS],
+            declarations: declarations_in,
+            curve_names: [None; SHARED_CURVES],
+            curve_declarations: curve_declarations_out,
+        },
+    )
+}
+
+impl UISharedData {
+    /// Forget the names declared by an older compile. Called with the generation of the payload
+    /// before the editor function runs, the names the new code declares then get fresh slots.
+    pub fn set_generation(&mut self, generation: usize) {
+        if generation == self.generation {
+            return;
+        }
+        self.generation = generation;
+        // Declarations from the process function of this compile may already be in
+        self.names.retain(|(_, _, _, g)| *g >= generation);
+        self.curve_names = [None; SHARED_CURVES];
+        self.meters = [MeterReading::default(); SHARED_METERS];
+    }
+
+    /// Pull in the latest values and stream data published by the process function.
+    /// Called once per frame before the editor function runs.
+    pub fn update(&mut self) {
+        self.values = *self.values_out.read();
+        for stream in self.streams.iter_mut() {
+            stream.consume();
+        }
+        let mut blocks = [MeterBlock::default(); SHARED_METERS];
+        while let Some((index, block)) = self.meter_blocks.pop() {
+            blocks[index].merge(&block);
+        }
+        for (reading, block) in self.meters.iter_mut().zip(blocks.iter()) {
+            // Without new samples the previous reading is kept
+            if block.count > 0 {
+                *reading = MeterReading {
+                    peak: block.peak,
+                    rms: (block.sum_squares / block.count as f32).sqrt(),
+                };
+            }
+        }
+        while let Some(declaration) = self.declarations.pop() {
+            if declaration.generation < self.generation {
+                continue;
+            }
+            self.names.push((
+                declaration.kind,
+                declaration.name.as_str().to_string(),
+                declaration.index,
+                declaration.generation,
+            ));
+        }
+        self.console.update();
+    }
+
+    /// Slot of the curve called `name`, taking a free one if it doesn't exist.
+    /// Returns None once all `SHARED_CURVES` are in use.
+    pub fn declare_curve(&mut self, name: &str) -> Option<usize> {
+        declare_name(
+            &mut self.curve_names,
+            &mut self.curve_declarations,
+            SharedKind::Curve,
+            name,
+            self.generation,
+        )
+    }
+
+    /// Slot of the value, stream or meter called `name`, None until the process function has
+    /// declared it and the editor has picked up the declaration in `update`.
+    pub fn find(&self, kind: SharedKind, name: &str) -> Option<usize> {
+        let name = ChannelName::new(name);
+        self.names
+            .iter()
+            .find(|(k, n, _, _)| *k == kind && n == name.as_str())
+            .map(|(_, _, index, _)| *index)
+    }
+
+    /// Print a message to the console, see `ConsoleInput::print`
+    pub fn print(&mut self, text: &str, value: LogValue) -> bool {
+        self.console.print(text, value)
+    }
+}
+
+impl DSPSharedData {
+    /// Same as `UISharedData::set_generation` for the process side. Doesn't allocate.
+    pub fn set_generation(&mut self, generation: usize) {
+        if generation == self.generation {
+            return;
+        }
+        self.generation = generation;
+        self.values = [0.0; SHARED_VALUES];
+        self.value_names = [None; SHARED_VALUES];
+        self.stream_names = [None; SHARED_STREAMS];
+        self.meter_names = [None; SHARED_METERS];
+        self.meter_blocks = [MeterBlock::default(); SHARED_METERS];
+        for name in self.curve_names.iter_mut() {
+            if matches!(name, Some((_, g)) if *g < generation) {
+                *name = None;
+            }
+        }
+    }
+
+    /// Publish the values set during this block to the editor.
+    /// Called after each run of the process function.
+    pub fn publish(&mut self) {
+        self.values_in.write(self.values);
+        for (index, block) in self.meter_blocks.iter_mut().enumerate() {
+            // If the editor is behind the block is kept and grows until there's room again
+            if block.count > 0 && self.meter_queue.push((index, *block)).is_ok() {
+                *block = MeterBlock::default();
+            }
+        }
+        while let Some(declaration) = self.curve_declarations.pop() {
+            if declaration.generation >= self.generation {
+                self.curve_names[declaration.index] =
+                    Some((declaration.name, declaration.generation));
+            }
+        }
+    }
+
+    /// Add a sample to meter `index`
+    pub fn meter_push(&mut self, index: usize, v: f32) {
+        if let Some(block) = self.meter_blocks.get_mut(index) {
+            block.peak = block.peak.max(v.abs());
+            block.sum_squares += v * v;
+            block.count += 1;
+        }
+    }
+
+    /// Slot of the value, stream or meter called `name`, taking a free one if it doesn't exist.
+    /// Returns None once all slots of that kind are in use. Doesn't allocate.
+    pub fn declare(&mut self, kind: SharedKind, name: &str) -> Option<usize> {
+        let names = match kind {
+            SharedKind::Value => &mut self.value_names[..],
+            SharedKind::Stream => &mut self.stream_names[..],
+            SharedKind::Meter => &mut self.meter_names[..],
+            SharedKind::Curve => return None,
+        };
+        declare_name(names, &mut self.declarations, kind, name, self.generation)
+    }
+
+    /// Slot of the curve called `name`, None until the editor function has declared it
+    pub fn find_curve(&self, name: &str) -> Option<usize> {
+        let name = ChannelName::new(name);
+        self.curve_names
+            .iter()
+            .position(|n| matches!(n, Some((n, _)) if *n == name))
+    }
+}
+
+/// Passed to Sarus inside `SarusUIModelParams` as `UIShared { ptr: & }`
+#[repr(C)]
+pub struct UISharedRef {
+    pub ptr: *mut UISharedData,
+}
+
+/// Passed to Sarus inside `AudioData` as `DSPShared { ptr: & }`
+#[repr(C)]
+pub struct DSPSharedRef {
+    pub ptr: *mut DSPSharedData,
+}
+
+impl UISharedRef {
+    pub fn get(&mut self) -> &mut UISharedData {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+impl DSPSharedRef {
+    pub fn get(&mut self) -> &mut DSPSharedData {
+        unsafe { &mut *self.ptr }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::console::ConsoleOutput;
+    #[test]
+    fn test_shared_data() {
+        let console = ConsoleOutput::default().new_input(crate::console::LogSource::Editor);
+        let (mut ui, mut dsp) = new_shared_data(console);
+        let gain = dsp.declare(SharedKind::Value, "gain").unwrap();
+        let env = dsp.declare(SharedKind::Stream, "env").unwrap();
+        assert_eq!(dsp.declare(SharedKind::Value, "gain"), Some(gain));
+        assert_ne!(dsp.declare(SharedKind::Value, "other"), Some(gain));
+        dsp.values[gain] = 0.25;
+        for i in 0..3 {
+            dsp.streams[env].push(i as f32).unwrap();
+        }
+        assert_eq!(ui.find(SharedKind::Value, "gain"), None);
+        dsp.publish();
+        ui.update();
+        assert_eq!(ui.find(SharedKind::Value, "gain"), Some(gain));
+        assert_eq!(ui.find(SharedKind::Stream, "env"), Some(env));
+        assert_eq!(ui.find(SharedKind::Stream, "gain"), None);
+        assert_eq!(ui.values[gain], 0.25);
+        let stream: Vec<f32> = ui.streams[env].iter().copied().collect();
+        assert_eq!(&stream[stream.len() - 3..], &[0.0, 1.0, 2.0]);
+
+        let meter = dsp.declare(SharedKind::Meter, "out").unwrap();
+        // Two blocks published before the editor reads them, the peak of the first isn't lost
+        dsp.meter_push(meter, -0.8);
+        dsp.meter_push(meter, 0.2);
+        dsp.publish();
+        dsp.meter_push(meter, 0.2);
+        dsp.meter_push(meter, 0.2);
+        dsp.publish();
+        ui.update();
+        assert_eq!(ui.meters[meter].peak, 0.8);
+        assert!((ui.meters[meter].rms - 0.43589).abs() < 1e-4);
+        ui.update();
+        assert_eq!(ui.meters[meter].peak, 0.8);
+        dsp.meter_push(meter, 0.1);
+        dsp.publish();
+        ui.update();
+        assert_eq!(ui.meters[meter].peak, 0.1);
+        // Blocks that don't fit while the editor is behind are merged into the next one
+        for _ in 0..METER_QUEUE_SIZE + 10 {
+            dsp.meter_push(meter, 0.5);
+            dsp.publish();
+        }
+        dsp.meter_push(meter, -0.9);
+        dsp.publish();
+        ui.update();
+        assert_eq!(ui.meters[meter].peak, 0.5);
+        dsp.publish();
+        ui.update();
+        assert_eq!(ui.meters[meter].peak, 0.9);
+
+        let shape = ui.declare_curve("shape").unwrap();
+        assert_eq!(dsp.find_curve("shape"), None);
+        dsp.publish();
+        assert_eq!(dsp.find_curve("shape"), Some(shape));
+    }
+
+    #[test]
+    fn test_shared_data_recompile() {
+        let console = ConsoleOutput::default().new_input(crate::console::LogSource::Editor);
+        let (mut ui, mut dsp) = new_shared_data(console);
+        let gain = dsp.declare(SharedKind::Value, "gain").unwrap();
+        ui.declare_curve("shape").unwrap();
+        dsp.publish();
+        ui.update();
+        assert_eq!(ui.find(SharedKind::Value, "gain"), Some(gain));
+
+        // The process side picks up the new compile first
+        dsp.set_generation(1);
+        assert_eq!(dsp.find_curve("shape"), None);
+        let level = dsp.declare(SharedKind::Value, "level").unwrap();
+        assert_eq!(level, gain);
+        dsp.publish();
+        ui.update();
+        assert_eq!(ui.find(SharedKind::Value, "level"), Some(level));
+        ui.set_generation(1);
+        assert_eq!(ui.find(SharedKind::Value, "gain"), None);
+        assert_eq!(ui.find(SharedKind::Value, "level"), Some(level));
+        let env = ui.declare_curve("env").unwrap();
+        dsp.publish();
+        assert_eq!(dsp.find_curve("env"), Some(env));
+
+        // Slots don't run out however often the names change
+        for generation in 2..SHARED_VALUES * 2 {
+            dsp.set_generation(generation);
+            let name = format!("value{}", generation);
+            assert!(dsp.declare(SharedKind::Value, &name).is_some());
+            ui.set_generation(generation);
+            ui.update();
+        }
+        assert_eq!(ui.names.len(), 1);
+        // A declaration from an older compile that was still in flight is ignored
+        ui.declare_curve("old").unwrap();
+        dsp.set_generation(SHARED_VALUES * 2);
+        dsp.publish();
+        assert_eq!(dsp.find_curve("old"), None);
+    }
+}
@@ -0,0 +1,155 @@
+use egui::{InputState, Key};
+use serde::{Deserialize, Serialize};
+
+/// Key bindings for the code editor window, stored in `Config::shortcuts`.
+///
+/// Bindings are written like `Ctrl+Shift+S`. `Ctrl` is the command key on macOS.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Shortcuts {
+    pub compile: String,
+    pub save: String,
+    pub new: String,
+    pub reload: String,
+    pub find: String,
+}
+
+impl Default for Shortcuts {
+    fn default() -> Self {
+        Shortcuts {
+            compile: "Ctrl+B".to_string(),
+            save: "Ctrl+S".to_string(),
+            new: "Ctrl+N".to_string(),
+            reload: "Ctrl+R".to_string(),
+            find: "Ctrl+F".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyBinding {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub key: Key,
+}
+
+impl KeyBinding {
+    pub fn parse(s: &str) -> Option<KeyBinding> {
+        let mut binding = KeyBinding {
+            ctrl: false,
+            shift: false,
+            alt: false,
+            key: Key::Escape,
+        };
+        let mut key = None;
+        for part in s.split('+').map(|part| part.trim()) {
+            match part.to_lowercase().as_str() {
+                "ctrl" | "cmd" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                "alt" => binding.alt = true,
+                _ => key = Some(key_from_name(part)?),
+            }
+        }
+        binding.key = key?;
+        Some(binding)
+    }
+
+    fn modifiers_match(&self, input: &InputState) -> bool {
+        input.modifiers.command == self.ctrl
+            && input.modifiers.shift == self.shift
+            && input.modifiers.alt == self.alt
+    }
+
+    /// True on the frame the binding was pressed
+    pub fn pressed(&self, input: &InputState) -> bool {
+        input.key_pressed(self.key) && self.modifiers_match(input)
+    }
+
+    /// True while the binding is held down
+    pub fn down(&self, input: &InputState) -> bool {
+        input.key_down(self.key) && self.modifiers_match(input)
+    }
+
+    /// True on the frame the key of the binding was released
+    pub fn released(&self, input: &InputState) -> bool {
+        input.key_released(self.key) && self.modifiers_match(input)
+    }
+}
+
+/// True on the frame the binding `s` was pressed. Invalid bindings are never pressed.
+pub fn binding_pressed(s: &str, input: &InputState) -> bool {
+    KeyBinding::parse(s).map_or(false, |binding| binding.pressed(input))
+}
+
+pub fn key_from_name(name: &str) -> Option<Key> {
+    let key = match name.to_lowercase().as_str() {
+        "down" | "arrowdown" => Key::ArrowDown,
+        "left" | "arrowleft" => Key::ArrowLeft,
+        "right" | "arrowright" => Key::ArrowRight,
+        "up" | "arrowup" => Key::ArrowUp,
+        "esc" | "escape" => Key::Escape,
+        "tab" => Key::Tab,
+        "backspace" => Key::Backspace,
+        "enter" | "return" => Key::Enter,
+        "space" => Key::Space,
+        "insert" => Key::Insert,
+        "delete" => Key::Delete,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "0" => Key::Num0,
+        "1" => Key::Num1,
+        "2" => Key::Num2,
+        "3" => Key::Num3,
+        "4" => Key::Num4,
+        "5" => Key::Num5,
+        "6" => Key::Num6,
+        "7" => Key::Num7,
+        "8" => Key::Num8,
+        "9" => Key::Num9,
+        "a" => Key::A,
+        "b" => Key::B,
+        "c" => Key::C,
+        "d" => Key::D,
+        "e" => Key::E,
+        "f" => Key::F,
+        "g" => Key::G,
+        "h" => Key::H,
+        "i" => Key::I,
+        "j" => Key::J,
+        "k" => Key::K,
+        "l" => Key::L,
+        "m" => Key::M,
+        "n" => Key::N,
+        "o" => Key::O,
+        "p" => Key::P,
+        "q" => Key::Q,
+        "r" => Key::R,
+        "s" => Key::S,
+        "t" => Key::T,
+        "u" => Key::U,
+        "v" => Key::V,
+        "w" => Key::W,
+        "x" => Key::X,
+        "y" => Key::Y,
+        "z" => Key::Z,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_parse_key_binding() {
+        let binding = KeyBinding::parse("Ctrl+Shift+S").unwrap();
+        assert!(binding.ctrl && binding.shift && !binding.alt);
+        assert_eq!(binding.key, Key::S);
+        assert_eq!(KeyBinding::parse("space").unwrap().key, Key::Space);
+        assert!(KeyBinding::parse("Ctrl+").is_none());
+        assert!(KeyBinding::parse("Ctrl+Foo").is_none());
+    }
+}
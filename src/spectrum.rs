@@ -0,0 +1,233 @@
+use std::f32::consts::PI;
+
+use egui::{pos2, vec2, Align2, Color32, Response, Sense, Stroke, TextStyle, Ui};
+
+use crate::correlation_match::{complex::Complex, fft::Fft};
+
+pub const SPECTRUM_MIN_DB: f32 = -120.0;
+pub const SPECTRUM_MAX_DB: f32 = 6.0;
+const MIN_FREQ: f32 = 10.0;
+const GRID_FREQS: [f32; 10] = [
+    20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 2000.0, 5000.0, 10000.0, 20000.0,
+];
+const GRID_DB_STEP: f32 = 12.0;
+
+/// Windowed FFT magnitude of the latest samples of a channel, with averaging and peak hold.
+pub struct SpectrumAnalyzer {
+    size: usize,
+    // The FFT and buffers are allocated by the first update, so channels that are never shown
+    // as a spectrum don't pay for them
+    fft: Option<Fft>,
+    window: Vec<f32>,
+    buffer: Vec<Complex>,
+    /// Averaged magnitude in dB of each bin from DC up to nyquist, empty until the first update
+    pub magnitudes_db: Vec<f32>,
+    pub peaks_db: Vec<f32>,
+    /// How much of the previous spectrum is kept each update, 0.0 is no averaging
+    pub averaging: f32,
+    pub peak_hold: bool,
+}
+
+impl SpectrumAnalyzer {
+    /// `size` is rounded down to a power of two
+    pub fn new(size: usize) -> Self {
+        let size = if size.is_power_of_two() {
+            size
+        } else {
+            size.next_power_of_two() / 2
+        }
+        .max(2);
+        SpectrumAnalyzer {
+            size,
+            fft: None,
+            window: Vec::new(),
+            buffer: Vec::new(),
+            magnitudes_db: Vec::new(),
+            peaks_db: Vec::new(),
+            averaging: 0.7,
+            peak_hold: false,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    fn allocate(&mut self) {
+        let size = self.size;
+        // Hann window
+        self.window = (0..size)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / size as f32).cos())
+            .collect();
+        self.buffer = vec![(0., 0.).into(); size];
+        self.magnitudes_db = vec![SPECTRUM_MIN_DB; size / 2 + 1];
+        self.peaks_db = vec![SPECTRUM_MIN_DB; size / 2 + 1];
+        self.fft = Some(Fft::new(size));
+    }
+
+    /// Analyze the last `size()` values of `samples`, oldest first
+    pub fn update<'a>(&mut self, samples: impl DoubleEndedIterator<Item = &'a f32>) {
+        let size = self.size();
+        if self.fft.is_none() {
+            self.allocate();
+        }
+        for z in self.buffer.iter_mut() {
+            *z = (0., 0.).into();
+        }
+        for (n, v) in samples.rev().take(size).enumerate() {
+            let i = size - 1 - n;
+            let v = if v.is_finite() { *v } else { 0.0 };
+            self.buffer[i] = (v * self.window[i], 0.0).into();
+        }
+        if let Some(fft) = &self.fft {
+            fft.fft(&mut self.buffer);
+        }
+        // A full scale sine reads 0 dB, the Hann window halves the amplitude
+        let scale = 4.0 / size as f32;
+        for (bin, z) in self.buffer[..size / 2 + 1].iter().enumerate() {
+            let db = (20.0 * (z.abs() * scale).max(1e-9).log10()).max(SPECTRUM_MIN_DB);
+            let average = &mut self.magnitudes_db[bin];
+            *average = *average * self.averaging + db * (1.0 - self.averaging);
+            if self.peak_hold {
+                self.peaks_db[bin] = self.peaks_db[bin].max(*average);
+            }
+        }
+    }
+
+    pub fn reset_peaks(&mut self) {
+        for peak in self.peaks_db.iter_mut() {
+            *peak = SPECTRUM_MIN_DB;
+        }
+    }
+
+    pub fn bin_frequency(&self, bin: usize, sample_rate: f32) -> f32 {
+        bin as f32 * sample_rate / self.size() as f32
+    }
+}
+
+/// Spectrum plot on a log frequency axis from `MIN_FREQ` to nyquist.
+pub fn spectrum_ui(ui: &mut Ui, spectrum: &SpectrumAnalyzer, sample_rate: f32) -> Response {
+    let width = ui.available_width();
+    let (response, painter) = ui.allocate_painter(vec2(width, width * 0.6), Sense::hover());
+    let rect = response.rect;
+    let nyquist = sample_rate * 0.5;
+    let log_min = MIN_FREQ.log10();
+    let log_max = nyquist.max(MIN_FREQ * 2.0).log10();
+    let x_from_freq = |freq: f32| {
+        let t = (freq.max(MIN_FREQ).log10() - log_min) / (log_max - log_min);
+        rect.left() + t * rect.width()
+    };
+    let freq_from_x = |x: f32| {
+        let t = (x - rect.left()) / rect.width();
+        10f32.powf(log_min + t * (log_max - log_min))
+    };
+    let y_from_db = |db: f32| {
+        let t = (db - SPECTRUM_MIN_DB) / (SPECTRUM_MAX_DB - SPECTRUM_MIN_DB);
+        rect.bottom() - t.clamp(0.0, 1.0) * rect.height()
+    };
+
+    let visuals = ui.visuals().clone();
+    painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+    let grid_stroke = Stroke::new(1.0, visuals.widgets.noninteractive.bg_stroke.color);
+    let text_color = visuals.weak_text_color();
+    for freq in GRID_FREQS.iter().filter(|freq| **freq < nyquist) {
+        let x = x_from_freq(*freq);
+        painter.line_segment([pos2(x, rect.top()), pos2(x, rect.bottom())], grid_stroke);
+        let label = if *freq >= 1000.0 {
+            format!("{}k", freq / 1000.0)
+        } else {
+            format!("{}", freq)
+        };
+        painter.text(
+            pos2(x + 2.0, rect.bottom() - 2.0),
+            Align2::LEFT_BOTTOM,
+            label,
+            TextStyle::Small,
+            text_color,
+        );
+    }
+    let mut db = 0.0;
+    while db > SPECTRUM_MIN_DB {
+        let y = y_from_db(db);
+        painter.line_segment([pos2(rect.left(), y), pos2(rect.right(), y)], grid_stroke);
+        painter.text(
+            pos2(rect.left() + 2.0, y),
+            Align2::LEFT_BOTTOM,
+            format!("{} dB", db),
+            TextStyle::Small,
+            text_color,
+        );
+        db -= GRID_DB_STEP;
+    }
+
+    let line = |values: &[f32], stroke: Stroke| {
+        let mut last = None;
+        for (bin, db) in values.iter().enumerate().skip(1) {
+            let freq = spectrum.bin_frequency(bin, sample_rate);
+            if freq < MIN_FREQ {
+                continue;
+            }
+            let p = pos2(x_from_freq(freq), y_from_db(*db));
+            if let Some(last) = last {
+                painter.line_segment([last, p], stroke);
+            }
+            last = Some(p);
+        }
+    };
+    if spectrum.peak_hold {
+        line(
+            &spectrum.peaks_db,
+            Stroke::new(1.0, Color32::from_rgb(230, 140, 40)),
+        );
+    }
+    line(
+        &spectrum.magnitudes_db,
+        Stroke::new(1.5, visuals.selection.bg_fill),
+    );
+
+    if let Some(pos) = response
+        .hover_pos()
+        .filter(|_| !spectrum.magnitudes_db.is_empty())
+    {
+        let freq = freq_from_x(pos.x);
+        let bin = ((freq / sample_rate * spectrum.size() as f32).round() as usize)
+            .min(spectrum.magnitudes_db.len() - 1);
+        painter.text(
+            rect.right_top() + vec2(-4.0, 4.0),
+            Align2::RIGHT_TOP,
+            format!("{:.0} Hz {:.1} dB", freq, spectrum.magnitudes_db[bin]),
+            TextStyle::Small,
+            visuals.text_color(),
+        );
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_sine_peak() {
+        let size = 1024;
+        let mut spectrum = SpectrumAnalyzer::new(size);
+        spectrum.averaging = 0.0;
+        // Exactly on bin 64
+        let samples: Vec<f32> = (0..size)
+            .map(|i| (2.0 * PI * 64.0 * i as f32 / size as f32).sin())
+            .collect();
+        spectrum.update(samples.iter());
+        let (peak_bin, peak_db) = spectrum.magnitudes_db.iter().enumerate().fold(
+            (0, SPECTRUM_MIN_DB),
+            |max, (bin, db)| {
+                if *db > max.1 {
+                    (bin, *db)
+                } else {
+                    max
+                }
+            },
+        );
+        assert_eq!(peak_bin, 64);
+        assert!(peak_db.abs() < 0.1);
+        assert_eq!(SpectrumAnalyzer::new(1000).size(), 512);
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+
+use egui::{Color32, Ui};
+
+use crate::heap_data::Heap;
+
+/// Arrays longer than this only show their first items
+const MAX_ARRAY_ITEMS: usize = 64;
+// Guards against structs that contain themselves
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    F32,
+    I64,
+    Bool,
+    /// References and slices, shown as the address
+    Address,
+    Struct(String),
+    Array(Box<FieldType>, usize),
+}
+
+impl FieldType {
+    fn parse(tokens: &[&str]) -> Option<FieldType> {
+        match tokens {
+            ["f32"] => Some(FieldType::F32),
+            ["i64"] => Some(FieldType::I64),
+            ["bool"] => Some(FieldType::Bool),
+            ["&", ..] => Some(FieldType::Address),
+            ["[", inner @ .., ";", len, "]"] => Some(FieldType::Array(
+                Box::new(Self::parse(inner)?),
+                len.parse().ok()?,
+            )),
+            [name] if name.chars().all(|c| c.is_alphanumeric() || c == '_') => {
+                Some(FieldType::Struct(name.to_string()))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Field layout of the Sarus structs declared in a script, used to read a `ProcessState` from
+/// a copy of its memory.
+///
+/// The structs are read from the source text, so ones that come from included files are not
+/// known. Fields use C layout, which is also how Sarus lays out the structs shared with Rust.
+/// The layout is only used if its size matches the state allocated by the compiler.
+#[derive(Debug, Clone)]
+pub struct StateLayout {
+    pub name: String,
+    pub size: usize,
+    /// Size of the state the compiler allocated, assumed to be `size` until it's set
+    pub compiled_size: usize,
+    structs: HashMap<String, Vec<(String, FieldType)>>,
+}
+
+impl StateLayout {
+    /// None if `name` isn't declared in `code`, or uses a struct that isn't
+    pub fn from_code(code: &str, name: &str) -> Option<StateLayout> {
+        let mut layout = StateLayout {
+            name: name.to_string(),
+            size: 0,
+            compiled_size: 0,
+            structs: parse_structs(code),
+        };
+        layout.size = layout
+            .size_align(&FieldType::Struct(name.to_string()), 0)?
+            .0;
+        layout.compiled_size = layout.size;
+        Some(layout)
+    }
+
+    /// Whether the layout read from the code agrees with the compiled state
+    pub fn matches_compiled(&self) -> bool {
+        self.size == self.compiled_size
+    }
+
+    fn size_align(&self, ty: &FieldType, depth: usize) -> Option<(usize, usize)> {
+        if depth > MAX_DEPTH {
+            return None;
+        }
+        Some(match ty {
+            FieldType::F32 => (4, 4),
+            FieldType::I64 | FieldType::Address => (8, 8),
+            FieldType::Bool => (1, 1),
+            FieldType::Array(ty, len) => {
+                let (size, align) = self.size_align(ty, depth + 1)?;
+                (size * len, align)
+            }
+            FieldType::Struct(name) => {
+                let mut offset = 0;
+                let mut struct_align = 1;
+                for (_, ty) in self.structs.get(name)? {
+                    let (size, align) = self.size_align(ty, depth + 1)?;
+                    offset = align_to(offset, align) + size;
+                    struct_align = struct_align.max(align);
+                }
+                (align_to(offset, struct_align), struct_align)
+            }
+        })
+    }
+
+    /// Offset of each field of struct `name`
+    fn fields(&self, name: &str) -> Vec<(&str, &FieldType, usize)> {
+        let mut fields = Vec::new();
+        let mut offset = 0;
+        for (field, ty) in self.structs.get(name).into_iter().flatten() {
+            let (size, align) = match self.size_align(ty, 0) {
+                Some(size_align) => size_align,
+                None => break,
+            };
+            offset = align_to(offset, align);
+            fields.push((field.as_str(), ty, offset));
+            offset += size;
+        }
+        fields
+    }
+}
+
+fn align_to(offset: usize, align: usize) -> usize {
+    offset + (align - offset % align) % align
+}
+
+fn tokenize(code: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    for line in code.lines() {
+        let line = line.split("//").next().unwrap_or("");
+        let mut start = None;
+        for (i, c) in line.char_indices() {
+            let word = c.is_alphanumeric() || c == '_';
+            match (start, word) {
+                (None, true) => start = Some(i),
+                (Some(s), false) => {
+                    tokens.push(&line[s..i]);
+                    start = None;
+                }
+                _ => (),
+            }
+            if !word && !c.is_whitespace() {
+                tokens.push(&line[i..i + c.len_utf8()]);
+            }
+        }
+        if let Some(s) = start {
+            tokens.push(&line[s..]);
+        }
+    }
+    tokens
+}
+
+/// Fields of every `struct Name { field: type, ... }` in `code`. Fields without a type are f32.
+fn parse_structs(code: &str) -> HashMap<String, Vec<(String, FieldType)>> {
+    let tokens = tokenize(code);
+    let mut structs = HashMap::new();
+    let mut i = 0;
+    while i + 2 < tokens.len() {
+        if tokens[i] != "struct" || tokens[i + 2] != "{" {
+            i += 1;
+            continue;
+        }
+        let name = tokens[i + 1].to_string();
+        i += 3;
+        let mut fields = Vec::new();
+        let mut valid = true;
+        while i < tokens.len() && tokens[i] != "}" {
+            let field = tokens[i].to_string();
+            i += 1;
+            let mut type_tokens = Vec::new();
+            if tokens.get(i) == Some(&":") {
+                i += 1;
+                let mut brackets = 0;
+                while i < tokens.len() {
+                    match tokens[i] {
+                        "[" => brackets += 1,
+                        "]" => brackets -= 1,
+                        "," | "}" if brackets == 0 => break,
+                        _ => (),
+                    }
+                    type_tokens.push(tokens[i]);
+                    i += 1;
+                }
+            }
+            if tokens.get(i) == Some(&",") {
+                i += 1;
+            }
+            let ty = if type_tokens.is_empty() {
+                Some(FieldType::F32)
+            } else {
+                FieldType::parse(&type_tokens)
+            };
+            match ty {
+                Some(ty) => fields.push((field, ty)),
+                None => valid = false,
+            }
+        }
+        if valid {
+            structs.insert(name, fields);
+        }
+        i += 1;
+    }
+    structs
+}
+
+fn read<const N: usize>(data: &[u8], offset: usize) -> Option<[u8; N]> {
+    let mut bytes = [0; N];
+    bytes.copy_from_slice(data.get(offset..offset + N)?);
+    Some(bytes)
+}
+
+fn value_ui(
+    ui: &mut Ui,
+    label: &str,
+    layout: &StateLayout,
+    ty: &FieldType,
+    data: &[u8],
+    offset: usize,
+) {
+    match ty {
+        FieldType::F32 => {
+            if let Some(bytes) = read::<4>(data, offset) {
+                let v = f32::from_ne_bytes(bytes);
+                if v.is_finite() {
+                    ui.label(format!("{}: {}", label, v));
+                } else {
+                    ui.colored_label(Color32::from_rgb(230, 60, 50), format!("{}: {}", label, v));
+                }
+            }
+        }
+        FieldType::I64 => {
+            if let Some(bytes) = read::<8>(data, offset) {
+                ui.label(format!("{}: {}", label, i64::from_ne_bytes(bytes)));
+            }
+        }
+        FieldType::Bool => {
+            if let Some(bytes) = read::<1>(data, offset) {
+                ui.label(format!("{}: {}", label, bytes[0] != 0));
+            }
+        }
+        FieldType::Address => {
+            if let Some(bytes) = read::<8>(data, offset) {
+                ui.label(format!("{}: {:#x}", label, u64::from_ne_bytes(bytes)));
+            }
+        }
+        FieldType::Array(item, len) => {
+            let item_size = layout
+                .size_align(item, 0)
+                .map(|(size, _)| size)
+                .unwrap_or(0);
+            egui::CollapsingHeader::new(format!("{}: [{}]", label, len))
+                .id_source((label, offset))
+                .show(ui, |ui| {
+                    for n in 0..(*len).min(MAX_ARRAY_ITEMS) {
+                        value_ui(
+                            ui,
+                            &n.to_string(),
+                            layout,
+                            item,
+                            data,
+                            offset + n * item_size,
+                        );
+                    }
+                    if *len > MAX_ARRAY_ITEMS {
+                        ui.label(format!("{} more", len - MAX_ARRAY_ITEMS));
+                    }
+                });
+        }
+        FieldType::Struct(name) => {
+            egui::CollapsingHeader::new(format!("{}: {}", label, name))
+                .id_source((label, offset))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for (field, ty, field_offset) in layout.fields(name) {
+                        value_ui(ui, field, layout, ty, data, offset + field_offset);
+                    }
+                });
+        }
+    }
+}
+
+/// Field values of a snapshot of the state described by `layout`
+pub fn state_ui(ui: &mut Ui, layout: &StateLayout, data: &[u8]) {
+    if !layout.matches_compiled() {
+        ui.colored_label(
+            Color32::from_rgb(230, 60, 50),
+            format!(
+                "{} is {} bytes when read from the code but {} bytes compiled, \
+                 it may use structs from included files",
+                layout.name, layout.size, layout.compiled_size
+            ),
+        );
+        return;
+    }
+    if data.len() != layout.size {
+        ui.label("Waiting for the process state");
+        return;
+    }
+    for (field, ty, offset) in layout.fields(&layout.name) {
+        value_ui(ui, field, layout, ty, data, offset);
+    }
+}
+
+/// Copy of the process state, made by the audio thread when the editor asks for one.
+#[derive(Default)]
+pub struct StateSnapshot {
+    requested: bool,
+    pub data: Vec<u8>,
+}
+
+impl StateSnapshot {
+    /// Ask for a copy of a state of `size` bytes. Allocates, so it's done on the editor side.
+    pub fn request(&mut self, size: usize) {
+        if self.data.len() != size {
+            self.data = vec![0; size];
+        }
+        self.requested = true;
+    }
+
+    /// Copy the state if a copy of this size was requested. Doesn't allocate.
+    pub fn capture(&mut self, state: &Heap) {
+        if self.requested && self.data.len() == state.size() {
+            let state = unsafe { std::slice::from_raw_parts(state.get_ptr(), state.size()) };
+            self.data.copy_from_slice(state);
+            self.requested = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_state_layout() {
+        let code = "
+struct ProcessState {
+    filter: Filter, // comment
+    count: i64,
+    on: bool,
+    history: [f32; 3],
+    buf: &[f32],
+}
+struct Filter { ic1eq, ic2eq, }
+";
+        let layout = StateLayout::from_code(code, "ProcessState").unwrap();
+        let fields: Vec<(&str, usize)> = layout
+            .fields("ProcessState")
+            .iter()
+            .map(|(name, _, offset)| (*name, *offset))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("filter", 0),
+                ("count", 8),
+                ("on", 16),
+                ("history", 20),
+                ("buf", 32)
+            ]
+        );
+        assert_eq!(layout.size, 40);
+        assert!(layout.matches_compiled());
+        assert!(
+            StateLayout::from_code("struct ProcessState { f: Unknown, }", "ProcessState").is_none()
+        );
+    }
+}
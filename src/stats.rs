@@ -0,0 +1,125 @@
+/// Statistics of the values sent to a debugger channel since the last reset.
+///
+/// Kept by the audio thread for every value, including ones that are dropped because the
+/// channel buffer is full, and published to the editor once per block.
+#[derive(Debug, Clone, Copy)]
+pub struct ChannelStats {
+    /// Number of values seen, including non finite ones
+    pub count: u64,
+    pub min: f32,
+    pub max: f32,
+    /// Mean of the previous block
+    pub dc_offset: f32,
+    pub nan: u64,
+    pub inf: u64,
+    pub denormal: u64,
+    /// Values that didn't reach the editor, because the buffer was full or not allocated yet
+    pub dropped: u64,
+    finite: u64,
+    sum: f64,
+    sum_squares: f64,
+    block_sum: f64,
+    block_count: u64,
+}
+
+impl Default for ChannelStats {
+    fn default() -> Self {
+        ChannelStats {
+            count: 0,
+            min: f32::INFINITY,
+            max: f32::NEG_INFINITY,
+            dc_offset: 0.0,
+            nan: 0,
+            inf: 0,
+            denormal: 0,
+            dropped: 0,
+            finite: 0,
+            sum: 0.0,
+            sum_squares: 0.0,
+            block_sum: 0.0,
+            block_count: 0,
+        }
+    }
+}
+
+impl ChannelStats {
+    /// NaN and Inf are only counted, they don't affect the other statistics
+    pub fn add(&mut self, v: f32) {
+        self.count += 1;
+        if v.is_nan() {
+            self.nan += 1;
+            return;
+        }
+        if v.is_infinite() {
+            self.inf += 1;
+            return;
+        }
+        if v.is_subnormal() {
+            self.denormal += 1;
+        }
+        self.finite += 1;
+        self.min = self.min.min(v);
+        self.max = self.max.max(v);
+        let v = v as f64;
+        self.sum += v;
+        self.sum_squares += v * v;
+        self.block_sum += v;
+        self.block_count += 1;
+    }
+
+    /// Update the DC offset from the values added since the previous call
+    pub fn end_block(&mut self) {
+        if self.block_count > 0 {
+            self.dc_offset = (self.block_sum / self.block_count as f64) as f32;
+        }
+        self.block_sum = 0.0;
+        self.block_count = 0;
+    }
+
+    pub fn mean(&self) -> f32 {
+        if self.finite > 0 {
+            (self.sum / self.finite as f64) as f32
+        } else {
+            0.0
+        }
+    }
+
+    pub fn rms(&self) -> f32 {
+        if self.finite > 0 {
+            (self.sum_squares / self.finite as f64).sqrt() as f32
+        } else {
+            0.0
+        }
+    }
+
+    pub fn peak_db(&self) -> f32 {
+        if self.finite > 0 {
+            20.0 * self.min.abs().max(self.max.abs()).log10()
+        } else {
+            f32::NEG_INFINITY
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_channel_stats() {
+        let mut stats = ChannelStats::default();
+        for v in [0.5, -1.0, 0.5, f32::NAN, f32::INFINITY, 1e-40].iter() {
+            stats.add(*v);
+        }
+        stats.end_block();
+        assert_eq!(stats.count, 6);
+        assert_eq!((stats.nan, stats.inf, stats.denormal), (1, 1, 1));
+        assert_eq!((stats.min, stats.max), (-1.0, 0.5));
+        assert!(stats.mean().abs() < 1e-6);
+        assert!((stats.rms() - (1.5f32 / 4.0).sqrt()).abs() < 1e-6);
+        assert!(stats.peak_db().abs() < 1e-6);
+        assert!(stats.dc_offset.abs() < 1e-6);
+        stats.add(2.0);
+        stats.end_block();
+        assert_eq!(stats.dc_offset, 2.0);
+    }
+}
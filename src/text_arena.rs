@@ -0,0 +1,36 @@
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+/// Per-frame scratch storage for strings created by Sarus editor code.
+///
+/// Pointers handed out by [`TextArena::alloc`] stay valid until the next [`TextArena::clear`],
+/// which is called at the start of every editor frame.
+#[derive(Default)]
+pub struct TextArena {
+    strings: Vec<CString>,
+}
+
+impl TextArena {
+    pub fn clear(&mut self) {
+        self.strings.clear();
+    }
+
+    pub fn alloc(&mut self, s: String) -> *const c_char {
+        let s = CString::new(s).unwrap_or_default();
+        let ptr = s.as_ptr();
+        self.strings.push(s);
+        ptr
+    }
+}
+
+/// Passed to Sarus inside `SarusUIModelParams` as `Text { ptr: & }`
+#[repr(C)]
+pub struct TextArenaRef {
+    pub ptr: *mut TextArena,
+}
+
+impl TextArenaRef {
+    pub fn get(&mut self) -> &mut TextArena {
+        unsafe { &mut *self.ptr }
+    }
+}
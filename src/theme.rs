@@ -0,0 +1,98 @@
+use std::{borrow::Cow, fs, path::Path};
+
+use egui::{vec2, Color32, CtxRef, FontDefinitions, FontFamily, TextStyle, Visuals};
+use log::error;
+use serde::{Deserialize, Serialize};
+
+const PROJECT_FONT: &str = "ProjectFont";
+
+/// Style of the plugin window, stored per project in `Config::themes`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct Theme {
+    pub dark_mode: bool,
+    pub accent_color: Option<[u8; 3]>,
+    pub font_size: f32,
+    /// Font file relative to the projects folder, used for proportional text
+    pub font_file: Option<String>,
+    pub interact_size: [f32; 2],
+    pub item_spacing: [f32; 2],
+    pub slider_width: f32,
+    /// Size of the plugin window, unless it was changed by the user
+    pub window_size: [f32; 2],
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            dark_mode: true,
+            accent_color: None,
+            font_size: 25.0,
+            font_file: None,
+            interact_size: [40.0, 40.0],
+            item_spacing: [8.0, 3.0],
+            slider_width: 300.0,
+            window_size: [700.0, 700.0],
+        }
+    }
+}
+
+impl Theme {
+    pub fn apply(&self, ctx: &CtxRef, projects_dir: &Path) {
+        let mut fonts = FontDefinitions::default();
+        if let Some(font_file) = &self.font_file {
+            match fs::read(projects_dir.join(font_file)) {
+                Ok(data) => {
+                    fonts
+                        .font_data
+                        .insert(PROJECT_FONT.to_string(), Cow::Owned(data));
+                    if let Some(family) = fonts.fonts_for_family.get_mut(&FontFamily::Proportional)
+                    {
+                        family.insert(0, PROJECT_FONT.to_string());
+                    }
+                }
+                Err(e) => error!("Could not load font file {} {}", font_file, e),
+            }
+        }
+        for (_text_style, (_family, size)) in fonts.family_and_size.iter_mut() {
+            *size = self.font_size;
+        }
+        ctx.set_fonts(fonts);
+
+        let mut style: egui::Style = (*ctx.style()).clone();
+        style.visuals = if self.dark_mode {
+            Visuals::dark()
+        } else {
+            Visuals::light()
+        };
+        if let Some([r, g, b]) = self.accent_color {
+            set_accent_color(&mut style.visuals, Color32::from_rgb(r, g, b));
+        }
+        style.spacing.interact_size = vec2(self.interact_size[0], self.interact_size[1]);
+        style.spacing.item_spacing = vec2(self.item_spacing[0], self.item_spacing[1]);
+        style.spacing.slider_width = self.slider_width;
+        ctx.set_style(style);
+    }
+}
+
+pub fn set_accent_color(visuals: &mut Visuals, color: Color32) {
+    visuals.selection.bg_fill = color;
+    visuals.hyperlink_color = color;
+}
+
+/// Change the size of all text styles, fonts are only rebuilt if the size actually changed.
+pub fn set_font_size(ctx: &CtxRef, size: f32) {
+    let current_size = ctx
+        .fonts()
+        .definitions()
+        .family_and_size
+        .get(&TextStyle::Body)
+        .map(|(_family, size)| *size);
+    if current_size != Some(size) {
+        let mut fonts = ctx.fonts().definitions().clone();
+        for (_text_style, (_family, font_size)) in fonts.family_and_size.iter_mut() {
+            *font_size = size;
+        }
+        ctx.set_fonts(fonts);
+    }
+}
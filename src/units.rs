@@ -18,18 +18,32 @@ where
         }
     }
 
-    pub fn consume(&mut self) {
-        for _ in 0..self.consumer.len() {
+    /// Returns the number of values consumed
+    pub fn consume(&mut self) -> usize {
+        self.consume_n(self.consumer.len())
+    }
+
+    /// Like `consume`, but leaves incomplete frames of `frame_len` values in the ring buffer
+    pub fn consume_frames(&mut self, frame_len: usize) -> usize {
+        let len = self.consumer.len();
+        self.consume_n(len - len % frame_len.max(1))
+    }
+
+    fn consume_n(&mut self, n: usize) -> usize {
+        let mut count = 0;
+        for _ in 0..n {
             if let Some(n) = self.consumer.pop() {
                 self.data[self.idx] = n;
                 self.idx += 1;
                 if self.idx >= self.data.len() {
                     self.idx = 0
                 }
+                count += 1;
             } else {
                 break;
             }
         }
+        count
     }
 
     pub fn iter(&self) -> std::iter::Chain<std::slice::Iter<'_, T>, std::slice::Iter<'_, T>> {